@@ -1,5 +1,10 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Vec};
+// Valores em stroops: a parte inteira é agrupada em milhares e os 7 decimais ficam juntos
+#![allow(clippy::inconsistent_digit_grouping)]
+use soroban_sdk::{contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env, IntoVal, Val, Vec};
+
+// `CreditScore` e o histórico de inadimplências nunca mudaram de layout
+const SCHEMA_VERSION: u32 = 1;
 
 // ==================== TYPES ====================
 
@@ -34,6 +39,29 @@ pub struct PaymentRecord {
     pub timestamp: u64,
 }
 
+#[derive(Clone)]
+#[contracttype]
+pub struct DefaultRecord {
+    pub loan_id: u64,
+    pub outstanding_amount: i128,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RenegotiationRecord {
+    pub loan_id: u64,
+    pub timestamp: u64,
+}
+
+// Aval dado a um empréstimo de terceiro; conta na exposição do garantidor
+#[derive(Clone)]
+#[contracttype]
+pub struct GuaranteeRecord {
+    pub loan_id: u64,
+    pub amount: i128,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct OffChainData {
@@ -43,10 +71,83 @@ pub struct OffChainData {
     pub credit_bureau: bool,
 }
 
+// ==================== ERRORS ====================
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum CreditScoreError {
+    NotInitialized = 1,
+    LoanContractNotSet = 2,
+    ContractPaused = 3,
+    AlreadyInitialized = 4,
+}
+
+// ==================== EXTERNAL CONTRACTS ====================
+
+// Espelho do escopo de pausa do contrato de governança
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum PauseScope {
+    None,
+    Originations,
+    All,
+}
+
+#[contractclient(name = "GovernanceClient")]
+pub trait GovernanceInterface {
+    fn get_pause(env: Env) -> PauseScope;
+}
+
+/// O credit score só para numa pausa total: pagamentos continuam sendo
+/// registrados durante uma pausa de originações.
+fn ensure_not_paused(env: &Env) -> Result<(), CreditScoreError> {
+    let governance: Option<Address> = env.storage().instance().get(&"GOVERNANCE");
+    match governance {
+        Some(governance) if GovernanceClient::new(env, &governance).get_pause() == PauseScope::All => {
+            Err(CreditScoreError::ContractPaused)
+        }
+        _ => Ok(()),
+    }
+}
+
 // ==================== STORAGE ====================
 
 const CREDIT_SCORE_KEY: &str = "SCORE";
 const PAYMENT_HISTORY_KEY: &str = "PAYMENTS";
+const DEFAULT_HISTORY_KEY: &str = "DEFAULTS";
+const RENEGOTIATION_HISTORY_KEY: &str = "RENEGOTIATIONS";
+const GUARANTEES_KEY: &str = "GUARANTEES";
+
+// TTL em ledgers (~5s cada): scores e históricos são renovados a cada escrita e leitura
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 120 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - 7 * DAY_IN_LEDGERS;
+
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn bump_persistent<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    if env.storage().persistent().has(key) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+/// Renova o score e todos os históricos do usuário
+fn bump_user(env: &Env, user: &Address) {
+    bump_persistent(env, &(CREDIT_SCORE_KEY, user.clone()));
+    bump_persistent(env, &(PAYMENT_HISTORY_KEY, user.clone()));
+    bump_persistent(env, &(DEFAULT_HISTORY_KEY, user.clone()));
+    bump_persistent(env, &(RENEGOTIATION_HISTORY_KEY, user.clone()));
+    bump_persistent(env, &(GUARANTEES_KEY, user.clone()));
+}
 
 fn get_credit_score(env: &Env, user: &Address) -> Option<CreditScore> {
     let key = (CREDIT_SCORE_KEY, user.clone());
@@ -56,6 +157,7 @@ fn get_credit_score(env: &Env, user: &Address) -> Option<CreditScore> {
 fn set_credit_score(env: &Env, score: &CreditScore) {
     let key = (CREDIT_SCORE_KEY, score.user.clone());
     env.storage().persistent().set(&key, score);
+    bump_persistent(env, &key);
 }
 
 fn get_payment_history(env: &Env, user: &Address) -> Option<Vec<PaymentRecord>> {
@@ -72,6 +174,75 @@ fn add_payment_record(env: &Env, user: &Address, record: PaymentRecord) {
     
     history.push_back(record);
     env.storage().persistent().set(&key, &history);
+    bump_persistent(env, &key);
+}
+
+fn get_default_history(env: &Env, user: &Address) -> Option<Vec<DefaultRecord>> {
+    let key = (DEFAULT_HISTORY_KEY, user.clone());
+    env.storage().persistent().get(&key)
+}
+
+fn add_default_record(env: &Env, user: &Address, record: DefaultRecord) {
+    let key = (DEFAULT_HISTORY_KEY, user.clone());
+    let mut history: Vec<DefaultRecord> = env.storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    
+    history.push_back(record);
+    env.storage().persistent().set(&key, &history);
+    bump_persistent(env, &key);
+}
+
+fn get_renegotiation_history(env: &Env, user: &Address) -> Option<Vec<RenegotiationRecord>> {
+    let key = (RENEGOTIATION_HISTORY_KEY, user.clone());
+    env.storage().persistent().get(&key)
+}
+
+fn add_renegotiation_record(env: &Env, user: &Address, record: RenegotiationRecord) {
+    let key = (RENEGOTIATION_HISTORY_KEY, user.clone());
+    let mut history: Vec<RenegotiationRecord> = env.storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    
+    history.push_back(record);
+    env.storage().persistent().set(&key, &history);
+    bump_persistent(env, &key);
+}
+
+fn get_guarantees(env: &Env, user: &Address) -> Option<Vec<GuaranteeRecord>> {
+    let key = (GUARANTEES_KEY, user.clone());
+    env.storage().persistent().get(&key)
+}
+
+/// Grava o aval do usuário no empréstimo, substituindo o anterior; `amount` zero remove o aval
+fn set_guarantee(env: &Env, user: &Address, loan_id: u64, amount: i128) {
+    let key = (GUARANTEES_KEY, user.clone());
+    let mut guarantees: Vec<GuaranteeRecord> = env.storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+
+    if let Some(index) = guarantees.iter().position(|record| record.loan_id == loan_id) {
+        guarantees.remove(index as u32);
+    }
+    if amount > 0 {
+        guarantees.push_back(GuaranteeRecord { loan_id, amount });
+    }
+
+    if guarantees.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &guarantees);
+        bump_persistent(env, &key);
+    }
+}
+
+fn guarantee_exposure(env: &Env, user: &Address) -> i128 {
+    get_guarantees(env, user)
+        .map(|guarantees| guarantees.iter().map(|record| record.amount).sum())
+        .unwrap_or(0)
 }
 
 // ==================== CALCULATOR ====================
@@ -102,11 +273,9 @@ fn calculate_on_chain_score(
     }
     
     let total_payments = payment_history.len();
-    let punctuality_score = if total_payments > 0 {
-        (on_time_count * 100) / total_payments
-    } else {
-        0
-    };
+    let punctuality_score = (on_time_count * 100)
+        .checked_div(total_payments)
+        .unwrap_or(0);
 
     let history_score = if total_payments > 50 {
         100
@@ -190,9 +359,52 @@ pub struct CreditScoreContract;
 
 #[contractimpl]
 impl CreditScoreContract {
-    pub fn initialize(env: Env, admin: Address) {
+    pub fn initialize(env: Env, admin: Address) -> Result<(), CreditScoreError> {
+        if env.storage().instance().has(&"ADMIN") {
+            return Err(CreditScoreError::AlreadyInitialized);
+        }
+        bump_instance(&env);
         admin.require_auth();
         env.storage().instance().set(&"ADMIN", &admin);
+        env.storage().instance().set(&"SCHEMA_VERSION", &SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// Troca o WASM mantendo os scores e históricos dos usuários, sem conversão necessária.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(CreditScoreError::NotInitialized)?;
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        env.events().publish((symbol_short!("upgraded"),), new_wasm_hash);
+        Ok(())
+    }
+
+    /// Hoje não converte nada, pois não existe layout anterior ao 1: apenas grava a versão.
+    /// Existe para manter o mesmo roteiro de upgrade dos outros contratos.
+    pub fn migrate(env: Env) -> Result<u32, CreditScoreError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(CreditScoreError::NotInitialized)?;
+        admin.require_auth();
+
+        if Self::get_schema_version(env.clone()) < SCHEMA_VERSION {
+            env.storage().instance().set(&"SCHEMA_VERSION", &SCHEMA_VERSION);
+            env.events().publish((symbol_short!("migrated"),), SCHEMA_VERSION);
+        }
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// Contratos implantados antes do versionamento não gravam a versão e estão no layout 1
+    pub fn get_schema_version(env: Env) -> u32 {
+        bump_instance(&env);
+        env.storage().instance().get(&"SCHEMA_VERSION").unwrap_or(1)
     }
 
     pub fn update_credit_score(
@@ -200,7 +412,9 @@ impl CreditScoreContract {
         user: Address,
         off_chain_data: OffChainData,
         credit_bureau_score: u32,
-    ) -> CreditScore {
+    ) -> Result<CreditScore, CreditScoreError> {
+        bump_instance(&env);
+        ensure_not_paused(&env)?;
         user.require_auth();
 
         let mut score = get_credit_score(&env, &user).unwrap_or(CreditScore {
@@ -245,7 +459,7 @@ impl CreditScoreContract {
         score.last_updated = env.ledger().timestamp();
 
         set_credit_score(&env, &score);
-        score
+        Ok(score)
     }
 
     pub fn record_payment(
@@ -254,11 +468,13 @@ impl CreditScoreContract {
         loan_id: u64,
         amount: i128,
         on_time: bool,
-    ) {
+    ) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
+        ensure_not_paused(&env)?;
         let loan_contract: Address = env.storage()
             .instance()
             .get(&"LOAN_CONTRACT")
-            .expect("Loan contract not set");
+            .ok_or(CreditScoreError::LoanContractNotSet)?;
         loan_contract.require_auth();
 
         let record = PaymentRecord {
@@ -277,13 +493,102 @@ impl CreditScoreContract {
             }
             set_credit_score(&env, &score);
         }
+        Ok(())
+    }
+
+    /// Registra o default de um empréstimo. Um empréstimo só entra uma vez no histórico:
+    /// repetições (nova tentativa do keeper após uma falha) não contam outro default.
+    pub fn record_default(
+        env: Env,
+        user: Address,
+        loan_id: u64,
+        outstanding_amount: i128,
+    ) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
+        ensure_not_paused(&env)?;
+        let loan_contract: Address = env.storage()
+            .instance()
+            .get(&"LOAN_CONTRACT")
+            .ok_or(CreditScoreError::LoanContractNotSet)?;
+        loan_contract.require_auth();
+
+        let history = get_default_history(&env, &user).unwrap_or(Vec::new(&env));
+        if history.iter().any(|record| record.loan_id == loan_id) {
+            return Ok(());
+        }
+
+        let record = DefaultRecord {
+            loan_id,
+            outstanding_amount,
+            timestamp: env.ledger().timestamp(),
+        };
+
+        add_default_record(&env, &user, record);
+
+        if let Some(mut score) = get_credit_score(&env, &user) {
+            score.default_count += 1;
+            set_credit_score(&env, &score);
+        }
+        Ok(())
+    }
+
+    pub fn record_renegotiation(env: Env, user: Address, loan_id: u64) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
+        ensure_not_paused(&env)?;
+        let loan_contract: Address = env.storage()
+            .instance()
+            .get(&"LOAN_CONTRACT")
+            .ok_or(CreditScoreError::LoanContractNotSet)?;
+        loan_contract.require_auth();
+
+        let record = RenegotiationRecord {
+            loan_id,
+            timestamp: env.ledger().timestamp(),
+        };
+
+        add_renegotiation_record(&env, &user, record);
+        Ok(())
+    }
+
+    /// Registra (ou, com `amount` zero, encerra) o aval de `guarantor` num empréstimo.
+    /// Enquanto o aval existe, o valor conta no limite de crédito do garantidor.
+    pub fn record_guarantee(
+        env: Env,
+        guarantor: Address,
+        loan_id: u64,
+        amount: i128,
+    ) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
+        ensure_not_paused(&env)?;
+        let loan_contract: Address = env.storage()
+            .instance()
+            .get(&"LOAN_CONTRACT")
+            .ok_or(CreditScoreError::LoanContractNotSet)?;
+        loan_contract.require_auth();
+
+        set_guarantee(&env, &guarantor, loan_id, amount);
+        Ok(())
+    }
+
+    /// Renova o TTL da instância e dos dados dos usuários informados (qualquer conta pode chamar)
+    pub fn extend_ttl(env: Env, users: Vec<Address>) {
+        bump_instance(&env);
+        for user in users.iter() {
+            bump_user(&env, &user);
+        }
     }
 
     pub fn get_score(env: Env, user: Address) -> Option<CreditScore> {
+        bump_instance(&env);
+        bump_user(&env, &user);
         get_credit_score(&env, &user)
     }
 
+    /// Avais em aberto consomem o limite como se fossem dívida própria
     pub fn can_borrow(env: Env, user: Address, amount: i128) -> bool {
+        bump_instance(&env);
+        bump_user(&env, &user);
+        let amount = amount + guarantee_exposure(&env, &user);
         if let Some(score) = get_credit_score(&env, &user) {
             match score.risk_level {
                 RiskLevel::Low => true,
@@ -295,17 +600,58 @@ impl CreditScoreContract {
         }
     }
 
-    pub fn set_loan_contract(env: Env, loan_contract: Address) {
+    pub fn set_loan_contract(env: Env, loan_contract: Address) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
         let admin: Address = env.storage()
             .instance()
             .get(&"ADMIN")
-            .expect("Admin not set");
+            .ok_or(CreditScoreError::NotInitialized)?;
         admin.require_auth();
 
         env.storage().instance().set(&"LOAN_CONTRACT", &loan_contract);
+        Ok(())
+    }
+
+    /// Contrato de governança consultado para a pausa de emergência
+    pub fn set_governance(env: Env, governance: Address) -> Result<(), CreditScoreError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(CreditScoreError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage().instance().set(&"GOVERNANCE", &governance);
+        Ok(())
     }
 
     pub fn get_payment_history(env: Env, user: Address) -> Option<Vec<PaymentRecord>> {
+        bump_instance(&env);
+        bump_user(&env, &user);
         get_payment_history(&env, &user)
     }
-}
\ No newline at end of file
+
+    pub fn get_default_history(env: Env, user: Address) -> Option<Vec<DefaultRecord>> {
+        bump_instance(&env);
+        bump_user(&env, &user);
+        get_default_history(&env, &user)
+    }
+
+    pub fn get_renegotiation_history(env: Env, user: Address) -> Option<Vec<RenegotiationRecord>> {
+        bump_instance(&env);
+        bump_user(&env, &user);
+        get_renegotiation_history(&env, &user)
+    }
+
+    pub fn get_guarantees(env: Env, user: Address) -> Option<Vec<GuaranteeRecord>> {
+        bump_instance(&env);
+        bump_user(&env, &user);
+        get_guarantees(&env, &user)
+    }
+
+    pub fn get_guarantee_exposure(env: Env, user: Address) -> i128 {
+        bump_instance(&env);
+        bump_user(&env, &user);
+        guarantee_exposure(&env, &user)
+    }
+}
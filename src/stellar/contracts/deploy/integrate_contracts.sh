@@ -47,7 +47,7 @@ echo -e "${GREEN}======================================${NC}"
 echo ""
 
 # INTEGRAÇÃO 1: Credit Score precisa conhecer o Loan Contract
-echo -e "${YELLOW}[1/1] Configurando Credit Score Contract...${NC}"
+echo -e "${YELLOW}[1/3] Configurando Credit Score Contract...${NC}"
 echo "Definindo Loan Contract autorizado para registrar pagamentos"
 
 stellar contract invoke \
@@ -66,6 +66,44 @@ else
 fi
 echo ""
 
+# INTEGRAÇÃO 2: Credit Score consulta a pausa de emergência da Governance
+echo -e "${YELLOW}[2/3] Configurando pausa de emergência no Credit Score...${NC}"
+
+stellar contract invoke \
+    --id $CREDIT_SCORE_CONTRACT \
+    --source $SOURCE_ACCOUNT \
+    --network $NETWORK \
+    -- \
+    set_governance \
+    --governance $GOVERNANCE_CONTRACT
+
+if [ $? -eq 0 ]; then
+    echo -e "${GREEN}✓ Credit Score integrado com Governance${NC}"
+else
+    echo -e "${RED}✗ Erro na integração${NC}"
+    exit 1
+fi
+echo ""
+
+echo -e "${YELLOW}[3/3] Configurando Governance Contract...${NC}"
+echo "Definindo Loan Contract autorizado a abrir claims do fundo de proteção"
+
+stellar contract invoke \
+    --id $GOVERNANCE_CONTRACT \
+    --source $SOURCE_ACCOUNT \
+    --network $NETWORK \
+    -- \
+    set_loan_contract \
+    --loan_contract $LOAN_CONTRACT
+
+if [ $? -eq 0 ]; then
+    echo -e "${GREEN}✓ Governance integrado com Loan Contract${NC}"
+else
+    echo -e "${RED}✗ Erro na integração${NC}"
+    exit 1
+fi
+echo ""
+
 echo -e "${GREEN}======================================${NC}"
 echo -e "${GREEN}  Integração Concluída!${NC}"
 echo -e "${GREEN}======================================${NC}"
@@ -73,7 +111,9 @@ echo ""
 echo -e "${YELLOW}Status das Integrações:${NC}"
 echo "✓ Credit Score ← Loan Contract (autorizado a registrar pagamentos)"
 echo "✓ Loan → Credit Score (pode verificar scores)"
-echo "✓ Loan → Governance (coleta taxas)"
+echo "✓ Loan → Governance (coleta taxas, pausa de emergência)"
+echo "✓ Credit Score → Governance (pausa de emergência)"
+echo "✓ Governance ← Loan Contract (claims abertos na inadimplência)"
 echo ""
 echo "Próximo passo: Testar as funcionalidades"
 echo "Execute: ./deploy/test_contracts.sh"
\ No newline at end of file
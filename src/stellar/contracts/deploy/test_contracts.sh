@@ -76,7 +76,8 @@ stellar contract invoke \
     --source test-investor \
     --network $NETWORK \
     -- \
-    get_protection_fund
+    get_protection_fund \
+    --asset $TOKEN_ADDRESS
 
 echo -e "${GREEN}✓ Teste 2 passou${NC}"
 echo ""
@@ -89,6 +90,8 @@ echo "  - Valor mínimo: 1.000 (em stroops: 10000000000)"
 echo "  - Taxa de juros: 500 (5%)"
 echo "  - Parcelas máximas: 12"
 echo "  - Score mínimo: 40"
+echo "  - Amortização: Price (parcelas fixas)"
+echo "  - Garantia: 20% do principal, no próprio ativo do card"
 
 CARD_RESULT=$(stellar contract invoke \
     --id $LOAN_CONTRACT \
@@ -97,11 +100,14 @@ CARD_RESULT=$(stellar contract invoke \
     -- \
     create_investment_card \
     --investor $INVESTOR_ADDRESS \
+    --asset $TOKEN_ADDRESS \
     --max_amount 100000000000 \
     --min_amount 10000000000 \
     --interest_rate 500 \
     --max_installments 12 \
-    --target_risk_level 40)
+    --target_risk_level 40 \
+    --amortization Price \
+    --collateral "{\"asset\":\"$TOKEN_ADDRESS\",\"ratio_bps\":2000}")
 
 INVESTMENT_CARD_ID=$(echo $CARD_RESULT | grep -oP '\d+' || echo "1")
 echo -e "${GREEN}✓ Card de investimento criado: ID $INVESTMENT_CARD_ID${NC}"
@@ -112,6 +118,10 @@ echo -e "${YELLOW}[TESTE 4] Criando card de solicitação de crédito...${NC}"
 echo "Parâmetros:"
 echo "  - Valor solicitado: 5.000 (em stroops: 50000000000)"
 echo "  - Parcelas desejadas: 6"
+echo "  - Amortização: Price (parcelas fixas)"
+echo "  - Taxa de juros: 500 (5%)"
+echo "  - Prazo de captação: 7 dias"
+echo "  - Garantia: nenhuma"
 echo "  - Descrição: Expansão de negócio freelancer"
 
 # Calcular datas de pagamento (próximos 6 meses)
@@ -125,6 +135,11 @@ PAYMENT_DATE_6=$((CURRENT_TIME + 180*DAY_IN_SECONDS))
 # Criar array JSON de datas
 PAYMENT_DATES="[$PAYMENT_DATE_1,$PAYMENT_DATE_2,$PAYMENT_DATE_3,$PAYMENT_DATE_4,$PAYMENT_DATE_5,$PAYMENT_DATE_6]"
 
+# Os credores podem aportar até o fim do prazo de captação
+FUNDING_DEADLINE=$((CURRENT_TIME + 7*DAY_IN_SECONDS))
+
+# Sem --collateral (argumento opcional) o pedido não exige garantia; com garantia
+# o tomador precisaria ter o valor no ativo do card para travá-lo na criação
 CARD_RESULT=$(stellar contract invoke \
     --id $LOAN_CONTRACT \
     --source test-borrower \
@@ -132,9 +147,13 @@ CARD_RESULT=$(stellar contract invoke \
     -- \
     create_request_card \
     --borrower $BORROWER_ADDRESS \
+    --asset $TOKEN_ADDRESS \
     --requested_amount 50000000000 \
     --desired_installments 6 \
     --preferred_payment_dates "$PAYMENT_DATES" \
+    --amortization Price \
+    --interest_rate 500 \
+    --funding_deadline $FUNDING_DEADLINE \
     --description "Expansão de negócio freelancer")
 
 REQUEST_CARD_ID=$(echo $CARD_RESULT | grep -oP '\d+' || echo "1")
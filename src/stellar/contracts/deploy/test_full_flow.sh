@@ -2,7 +2,7 @@
 
 # Script para testar o fluxo completo de empréstimo Mithril
 # 1. Aplicar para um card de investimento
-# 2. Aprovar uma aplicação
+# 2. Aprovar uma aplicação e confirmar o PIX de desembolso
 # 3. Realizar pagamento de parcela
 # 4. Verificar atualização de score
 
@@ -52,6 +52,9 @@ echo ""
 # Constantes
 DAY_IN_SECONDS=86400
 CURRENT_TIME=$(date +%s)
+# Conta admin dos contratos (registra o PIX de desembolso) e emissora do token de teste
+SOURCE_ACCOUNT=${SOURCE_ACCOUNT:-"mithril-admin"}
+TOKEN_ADMIN=${TOKEN_ADMIN:-$SOURCE_ACCOUNT}
 
 echo -e "${GREEN}======================================${NC}"
 echo -e "${GREEN}  PASSO 1: Aplicar para Card${NC}"
@@ -61,10 +64,23 @@ echo ""
 echo -e "${CYAN}O tomador irá aplicar para o card de investimento${NC}"
 echo "Card ID: $INVESTMENT_CARD_ID"
 echo "Valor solicitado: 5.000 (50000000000 stroops)"
+echo "Garantia exigida pelo card: 20% (10000000000 stroops)"
 echo ""
 
 read -p "Pressione ENTER para continuar..."
 
+# O card exige garantia de 20%, travada na carteira do tomador já na aplicação
+stellar contract invoke \
+    --id $TOKEN_ADDRESS \
+    --source $TOKEN_ADMIN \
+    --network $NETWORK \
+    -- \
+    mint \
+    --to $BORROWER_ADDRESS \
+    --amount 10000000000
+
+echo -e "${GREEN}✓ Garantia creditada ao tomador${NC}"
+
 APPLICATION_ID=$(stellar contract invoke \
     --id $LOAN_CONTRACT \
     --source test-borrower \
@@ -132,7 +148,40 @@ echo ""
 echo "LOAN_ID=$LOAN_ID" >> $TEST_ACCOUNTS
 
 echo -e "${MAGENTA}Status atual:${NC}"
-echo "→ Empréstimo criado e ativo"
+echo "→ Empréstimo criado como Pending"
+echo "→ Principal em custódia até a confirmação do PIX de desembolso"
+echo ""
+
+# O empréstimo só fica ativo quando o PIX de desembolso ao tomador é confirmado
+echo -e "${CYAN}Registrando e confirmando o PIX de desembolso...${NC}"
+PAYOUT_ID="payout-$LOAN_ID-$CURRENT_TIME"
+
+stellar contract invoke \
+    --id $LOAN_CONTRACT \
+    --source $SOURCE_ACCOUNT \
+    --network $NETWORK \
+    -- \
+    register_pix_payment \
+    --payment_id "$PAYOUT_ID" \
+    --order_id "order-$PAYOUT_ID" \
+    --amount 50000000000 \
+    --pix_type CreditPayout \
+    --related_loan_id $LOAN_ID
+
+stellar contract invoke \
+    --id $LOAN_CONTRACT \
+    --source $SOURCE_ACCOUNT \
+    --network $NETWORK \
+    -- \
+    update_pix_payment_status \
+    --payment_id "$PAYOUT_ID" \
+    --new_status Completed
+
+echo -e "${GREEN}✓ Desembolso confirmado${NC}"
+echo ""
+
+echo -e "${MAGENTA}Status atual:${NC}"
+echo "→ Empréstimo ativo"
 echo "→ Fundos transferidos para o tomador"
 echo "→ 12 parcelas a serem pagas"
 echo ""
@@ -199,6 +248,17 @@ echo ""
 
 read -p "Pressione ENTER para realizar o pagamento..."
 
+# Valor da primeira parcela, lido do cronograma gerado na ativação
+SCHEDULE=$(stellar contract invoke \
+    --id $LOAN_CONTRACT \
+    --source test-borrower \
+    --network $NETWORK \
+    -- \
+    get_schedule \
+    --loan_id $LOAN_ID)
+INSTALLMENT_AMOUNT=$(echo "$SCHEDULE" | grep -oP '"amount":"?\K\d+' | head -1)
+echo "Valor da parcela: $INSTALLMENT_AMOUNT stroops"
+
 # Realizar pagamento
 PAYMENT_RESULT=$(stellar contract invoke \
     --id $LOAN_CONTRACT \
@@ -206,7 +266,8 @@ PAYMENT_RESULT=$(stellar contract invoke \
     --network $NETWORK \
     -- \
     make_payment \
-    --loan_id $LOAN_ID)
+    --loan_id $LOAN_ID \
+    --amount $INSTALLMENT_AMOUNT)
 
 echo -e "${GREEN}✓ Pagamento realizado com sucesso!${NC}"
 echo "Resultado: $PAYMENT_RESULT"
@@ -1,5 +1,14 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env};
+use soroban_sdk::{contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token, Address, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec};
+
+// Layout de claims e do fundo de proteção: o 1 guardava só o valor pago no claim,
+// o 2 tinha um fundo único e o 3 separa o fundo e o claim por ativo
+const SCHEMA_VERSION: u32 = 3;
+// O fundo de proteção cobre 80% do principal em aberto de um empréstimo inadimplente
+const COVERAGE_BPS: i128 = 8000;
+// Pausas `All` mantidas no histórico; o contrato de empréstimo carrega a lista a cada
+// pagamento, então as mais antigas são descartadas quando o limite é atingido
+const MAX_PAUSE_PERIODS: u32 = 32;
 
 // ==================== TYPES ====================
 
@@ -11,6 +20,7 @@ pub struct FeeConfig {
     pub last_updated: u64,
 }
 
+// Fundo de proteção de um ativo; cada ativo aceito pelo contrato de empréstimo tem o seu
 #[derive(Clone)]
 #[contracttype]
 pub struct ProtectionFund {
@@ -19,6 +29,44 @@ pub struct ProtectionFund {
     pub active_claims: u32,
 }
 
+// Escopo da pausa de emergência consultada pelos contratos de empréstimo e credit score
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum PauseScope {
+    None,
+    Originations, // Bloqueia novos cards, aplicações e empréstimos; pagamentos continuam
+    All,          // Bloqueia qualquer alteração de estado
+}
+
+// Intervalo em que o contrato ficou pausado em `All`; `end` é o fim da pausa
+// ou, para a pausa em curso, o momento da consulta
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct PausePeriod {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum ClaimStatus {
+    Open,    // Aberto na inadimplência, aguardando pagamento
+    Paid,    // Cobertura repassada aos credores
+    Closed,  // Encerrado pelo admin sem pagamento
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct ProtectionClaim {
+    pub loan_id: u64,
+    pub asset: Address,   // Ativo do empréstimo; a cobertura sai do fundo desse ativo
+    pub principal: i128,  // Principal em aberto na data da inadimplência
+    pub payout: i128,     // Cobertura devida pelo fundo
+    pub status: ClaimStatus,
+    pub opened_at: u64,
+    pub updated_at: u64,
+}
+
 impl FeeConfig {
     pub fn calculate_transaction_fee(&self, amount: i128) -> i128 {
         (amount * self.transaction_fee as i128) / 10000
@@ -29,6 +77,133 @@ impl FeeConfig {
     }
 }
 
+// ==================== ERRORS ====================
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum GovernanceError {
+    NotInitialized = 1,
+    InsufficientAvailableFunds = 2,
+    LoanContractNotSet = 3,
+    InvalidClaimAmount = 4,
+    ClaimAlreadyExists = 5,
+    ClaimNotFound = 6,
+    ClaimNotOpen = 7,
+    InsufficientProtectionFund = 8,
+    AlreadyInitialized = 9,
+}
+
+// ==================== EXTERNAL CONTRACTS ====================
+
+// Espelho da posição de cada credor no contrato de empréstimo
+#[derive(Clone)]
+#[contracttype]
+pub struct LoanShare {
+    pub lender: Address,
+    pub amount: i128,
+}
+
+#[contractclient(name = "LoanClient")]
+pub trait LoanInterface {
+    fn get_loan_shares(env: Env, loan_id: u64) -> Option<Vec<LoanShare>>;
+}
+
+// ==================== TTL ====================
+
+// TTL em ledgers (~5s cada): a instância guarda taxas e fundo e é renovada a cada chamada
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 120 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - 7 * DAY_IN_LEDGERS;
+
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn bump_claim(env: &Env, loan_id: u64) {
+    let key = ("CLAIM", loan_id);
+    if env.storage().persistent().has(&key) {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+// ==================== PROTECTION FUND ====================
+
+/// Fundo do ativo informado. No layout 2 havia um único fundo, no ativo padrão
+/// (`TOKEN`), guardado em `PROTECTION_FUND`; ele vale até a migração movê-lo.
+fn load_fund(env: &Env, asset: &Address) -> Result<ProtectionFund, GovernanceError> {
+    if let Some(fund) = env.storage().instance().get(&("PROTECTION_FUND", asset.clone())) {
+        return Ok(fund);
+    }
+    let default_asset: Address = env.storage()
+        .instance()
+        .get(&"TOKEN")
+        .ok_or(GovernanceError::NotInitialized)?;
+    let legacy: Option<ProtectionFund> = env.storage().instance().get(&"PROTECTION_FUND");
+    match legacy {
+        Some(fund) if *asset == default_asset => Ok(fund),
+        _ => Ok(ProtectionFund {
+            total_balance: 0,
+            total_claims: 0,
+            active_claims: 0,
+        }),
+    }
+}
+
+fn save_fund(env: &Env, asset: &Address, fund: &ProtectionFund) {
+    env.storage().instance().set(&("PROTECTION_FUND", asset.clone()), fund);
+}
+
+// ==================== CLAIMS ====================
+
+fn emit_claim(env: &Env, topic: Symbol, claim: &ProtectionClaim) {
+    env.events().publish((topic, claim.loan_id), claim.clone());
+}
+
+/// Claims do layout 1 guardavam só o valor pago na hora (80% do valor pedido)
+/// e nunca deixavam de contar como ativos; os do layout 2 não registravam o ativo.
+/// Antes do layout 3 todos os claims eram no ativo padrão.
+fn migrate_claim(env: &Env, asset: &Address, fund: &mut ProtectionFund, loan_id: u64) {
+    let key = ("CLAIM", loan_id);
+    let raw: Option<Val> = env.storage().persistent().get(&key);
+    let raw = match raw {
+        Some(raw) => raw,
+        None => return,
+    };
+
+    if let Ok(mut fields) = Map::<Symbol, Val>::try_from_val(env, &raw) {
+        if !fields.contains_key(symbol_short!("asset")) {
+            fields.set(symbol_short!("asset"), asset.into_val(env));
+            env.storage().persistent().set(&key, &fields);
+            bump_claim(env, loan_id);
+        }
+        return;
+    }
+
+    let payout = match i128::try_from_val(env, &raw) {
+        Ok(payout) => payout,
+        Err(_) => return,
+    };
+    let claim = ProtectionClaim {
+        loan_id,
+        asset: asset.clone(),
+        principal: payout * 10000 / COVERAGE_BPS,
+        payout,
+        status: ClaimStatus::Paid,
+        opened_at: 0,  // Data não registrada no layout 1
+        updated_at: 0,
+    };
+    env.storage().persistent().set(&key, &claim);
+    bump_claim(env, loan_id);
+    fund.active_claims = fund.active_claims.saturating_sub(1);
+}
+
 // ==================== CONTRACT ====================
 
 #[contract]
@@ -42,7 +217,11 @@ impl GovernanceContract {
         token: Address,
         transaction_fee: u32,
         gas_fee: u32,
-    ) {
+    ) -> Result<(), GovernanceError> {
+        if env.storage().instance().has(&"ADMIN") {
+            return Err(GovernanceError::AlreadyInitialized);
+        }
+        bump_instance(&env);
         admin.require_auth();
         
         env.storage().instance().set(&"ADMIN", &admin);
@@ -62,98 +241,311 @@ impl GovernanceContract {
             active_claims: 0,
         };
         
-        env.storage().instance().set(&"PROTECTION_FUND", &protection_fund);
+        save_fund(&env, &token, &protection_fund);
+        env.storage().instance().set(&"SCHEMA_VERSION", &SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// Troca o WASM mantendo fundos, claims e a configuração de taxas. Vindo do layout 2,
+    /// o fundo único só passa a valer por ativo depois de `migrate`.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        env.events().publish((symbol_short!("upgraded"),), new_wasm_hash);
+        Ok(())
     }
 
+    /// Converte os claims dos empréstimos informados gravados em layouts anteriores,
+    /// move o fundo único para o fundo do ativo padrão e grava a versão atual.
+    /// `FeeConfig` mantém o layout original; pode ser chamada em lotes.
+    pub fn migrate(env: Env, loan_ids: Vec<u64>) -> Result<u32, GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
+        admin.require_auth();
+
+        let asset: Address = env.storage()
+            .instance()
+            .get(&"TOKEN")
+            .ok_or(GovernanceError::NotInitialized)?;
+        let mut fund = load_fund(&env, &asset)?;
+        for loan_id in loan_ids.iter() {
+            migrate_claim(&env, &asset, &mut fund, loan_id);
+        }
+        save_fund(&env, &asset, &fund);
+        env.storage().instance().remove(&"PROTECTION_FUND");
+
+        if Self::get_schema_version(env.clone()) < SCHEMA_VERSION {
+            env.storage().instance().set(&"SCHEMA_VERSION", &SCHEMA_VERSION);
+            env.events().publish((symbol_short!("migrated"),), SCHEMA_VERSION);
+        }
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// Contratos implantados antes do versionamento não gravam a versão e estão no layout 1
+    pub fn get_schema_version(env: Env) -> u32 {
+        bump_instance(&env);
+        env.storage().instance().get(&"SCHEMA_VERSION").unwrap_or(1)
+    }
+
+    /// Cobra a taxa de transação sobre `amount`, no ativo da operação
     pub fn collect_transaction_fee(
         env: Env,
         from: Address,
+        asset: Address,
         amount: i128,
-    ) -> i128 {
+    ) -> Result<i128, GovernanceError> {
+        bump_instance(&env);
+        from.require_auth();
+
         let fee_config: FeeConfig = env.storage()
             .instance()
             .get(&"FEE_CONFIG")
-            .unwrap();
+            .ok_or(GovernanceError::NotInitialized)?;
 
         let fee = (amount * fee_config.transaction_fee as i128) / 10000;
         
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(&env, &token_address);
+        let token_client = token::Client::new(&env, &asset);
         
         token_client.transfer(&from, &env.current_contract_address(), &fee);
 
-        fee
+        Ok(fee)
     }
 
+    /// Cobra a contribuição ao fundo de proteção do ativo da operação
     pub fn collect_gas_fee(
         env: Env,
         from: Address,
+        asset: Address,
         transaction_amount: i128,
-    ) -> i128 {
+    ) -> Result<i128, GovernanceError> {
+        bump_instance(&env);
+        from.require_auth();
+
         let fee_config: FeeConfig = env.storage()
             .instance()
             .get(&"FEE_CONFIG")
-            .unwrap();
+            .ok_or(GovernanceError::NotInitialized)?;
 
         let gas_fee = (transaction_amount * fee_config.gas_fee as i128) / 10000;
         
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(&env, &token_address);
+        let token_client = token::Client::new(&env, &asset);
         
         token_client.transfer(&from, &env.current_contract_address(), &gas_fee);
 
-        let mut fund: ProtectionFund = env.storage()
-            .instance()
-            .get(&"PROTECTION_FUND")
-            .unwrap();
+        let mut fund = load_fund(&env, &asset)?;
         
         fund.total_balance += gas_fee;
-        env.storage().instance().set(&"PROTECTION_FUND", &fund);
+        save_fund(&env, &asset, &fund);
 
-        gas_fee
+        Ok(gas_fee)
     }
 
-    pub fn claim_protection(
-        env: Env,
-        lender: Address,
-        loan_id: u64,
-        amount: i128,
-    ) -> bool {
-        lender.require_auth();
+    /// Abre o claim de um empréstimo inadimplente. Chamado pelo contrato de empréstimo,
+    /// que informa o ativo e o principal em aberto; cada empréstimo tem no máximo um claim.
+    pub fn open_claim(env: Env, loan_id: u64, asset: Address, principal: i128) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let loan_contract: Address = env.storage()
+            .instance()
+            .get(&"LOAN_CONTRACT")
+            .ok_or(GovernanceError::LoanContractNotSet)?;
+        loan_contract.require_auth();
+
+        if principal <= 0 {
+            return Err(GovernanceError::InvalidClaimAmount);
+        }
+        if env.storage().persistent().has(&("CLAIM", loan_id)) {
+            return Err(GovernanceError::ClaimAlreadyExists);
+        }
+
+        let mut fund = load_fund(&env, &asset)?;
+        fund.total_claims += 1;
+        fund.active_claims += 1;
+        save_fund(&env, &asset, &fund);
+
+        let now = env.ledger().timestamp();
+        let claim = ProtectionClaim {
+            loan_id,
+            asset,
+            principal,
+            payout: principal * COVERAGE_BPS / 10000,
+            status: ClaimStatus::Open,
+            opened_at: now,
+            updated_at: now,
+        };
+        env.storage().persistent().set(&("CLAIM", loan_id), &claim);
+        bump_claim(&env, loan_id);
+
+        emit_claim(&env, symbol_short!("claim_new"), &claim);
+        Ok(())
+    }
+
+    /// Paga a cobertura de um claim aberto aos detentores atuais das posições do
+    /// empréstimo, proporcionalmente ao principal de cada um. Qualquer conta pode chamar.
+    pub fn pay_claim(env: Env, loan_id: u64) -> Result<i128, GovernanceError> {
+        bump_instance(&env);
+        let mut claim: ProtectionClaim = env.storage()
+            .persistent()
+            .get(&("CLAIM", loan_id))
+            .ok_or(GovernanceError::ClaimNotFound)?;
+
+        if claim.status != ClaimStatus::Open {
+            return Err(GovernanceError::ClaimNotOpen);
+        }
+
+        let mut fund = load_fund(&env, &claim.asset)?;
+        if fund.total_balance < claim.payout {
+            return Err(GovernanceError::InsufficientProtectionFund);
+        }
 
-        let mut fund: ProtectionFund = env.storage()
+        let loan_contract: Address = env.storage()
             .instance()
-            .get(&"PROTECTION_FUND")
-            .unwrap();
+            .get(&"LOAN_CONTRACT")
+            .ok_or(GovernanceError::LoanContractNotSet)?;
+        let shares = LoanClient::new(&env, &loan_contract)
+            .get_loan_shares(&loan_id)
+            .ok_or(GovernanceError::ClaimNotFound)?;
+
+        let mut total_principal: i128 = 0;
+        for share in shares.iter() {
+            total_principal += share.amount;
+        }
 
-        if fund.total_balance < amount {
-            return false;
+        let token_client = token::Client::new(&env, &claim.asset);
+
+        // O arredondamento fica com o último credor para que a soma feche com o payout
+        let mut distributed: i128 = 0;
+        for (index, share) in shares.iter().enumerate() {
+            let portion = if index as u32 == shares.len() - 1 {
+                claim.payout - distributed
+            } else {
+                claim.payout * share.amount / total_principal
+            };
+            token_client.transfer(&env.current_contract_address(), &share.lender, &portion);
+            distributed += portion;
         }
 
-        let coverage = (amount * 80) / 100;
-        let payout = coverage.min(fund.total_balance);
+        fund.total_balance -= claim.payout;
+        fund.active_claims -= 1;
+        save_fund(&env, &claim.asset, &fund);
 
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &lender, &payout);
+        claim.status = ClaimStatus::Paid;
+        claim.updated_at = env.ledger().timestamp();
+        env.storage().persistent().set(&("CLAIM", loan_id), &claim);
+        bump_claim(&env, loan_id);
 
-        fund.total_balance -= payout;
-        fund.total_claims += 1;
-        fund.active_claims += 1;
-        
-        env.storage().instance().set(&"PROTECTION_FUND", &fund);
+        emit_claim(&env, symbol_short!("claim_pay"), &claim);
+        Ok(claim.payout)
+    }
 
-        env.storage().persistent().set(&("CLAIM", loan_id), &payout);
+    /// Abate do claim aberto o que os credores recuperaram fora do fundo (ex.: cobrança
+    /// dos avalistas). Chamado pelo contrato de empréstimo; a cobertura é recalculada
+    /// sobre o principal restante e o claim é encerrado se ele zerar.
+    pub fn reduce_claim(env: Env, loan_id: u64, recovered: i128) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let loan_contract: Address = env.storage()
+            .instance()
+            .get(&"LOAN_CONTRACT")
+            .ok_or(GovernanceError::LoanContractNotSet)?;
+        loan_contract.require_auth();
 
-        true
+        if recovered <= 0 {
+            return Err(GovernanceError::InvalidClaimAmount);
+        }
+        let mut claim: ProtectionClaim = env.storage()
+            .persistent()
+            .get(&("CLAIM", loan_id))
+            .ok_or(GovernanceError::ClaimNotFound)?;
+        if claim.status != ClaimStatus::Open {
+            return Err(GovernanceError::ClaimNotOpen);
+        }
+
+        claim.principal = (claim.principal - recovered).max(0);
+        claim.payout = claim.principal * COVERAGE_BPS / 10000;
+        claim.updated_at = env.ledger().timestamp();
+        let topic = if claim.principal == 0 {
+            let mut fund = load_fund(&env, &claim.asset)?;
+            fund.active_claims -= 1;
+            save_fund(&env, &claim.asset, &fund);
+            claim.status = ClaimStatus::Closed;
+            symbol_short!("claim_cls")
+        } else {
+            symbol_short!("claim_red")
+        };
+        env.storage().persistent().set(&("CLAIM", loan_id), &claim);
+        bump_claim(&env, loan_id);
+
+        emit_claim(&env, topic, &claim);
+        Ok(())
+    }
+
+    /// Encerra sem pagamento um claim aberto (ex.: inadimplência revertida)
+    pub fn close_claim(env: Env, loan_id: u64) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut claim: ProtectionClaim = env.storage()
+            .persistent()
+            .get(&("CLAIM", loan_id))
+            .ok_or(GovernanceError::ClaimNotFound)?;
+        if claim.status != ClaimStatus::Open {
+            return Err(GovernanceError::ClaimNotOpen);
+        }
+
+        let mut fund = load_fund(&env, &claim.asset)?;
+        fund.active_claims -= 1;
+        save_fund(&env, &claim.asset, &fund);
+
+        claim.status = ClaimStatus::Closed;
+        claim.updated_at = env.ledger().timestamp();
+        env.storage().persistent().set(&("CLAIM", loan_id), &claim);
+        bump_claim(&env, loan_id);
+
+        emit_claim(&env, symbol_short!("claim_cls"), &claim);
+        Ok(())
+    }
+
+    pub fn get_claim(env: Env, loan_id: u64) -> Option<ProtectionClaim> {
+        bump_instance(&env);
+        bump_claim(&env, loan_id);
+        env.storage().persistent().get(&("CLAIM", loan_id))
+    }
+
+    /// Contrato de empréstimo autorizado a abrir claims e consultado no pagamento
+    pub fn set_loan_contract(env: Env, loan_contract: Address) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
+        admin.require_auth();
+
+        env.storage().instance().set(&"LOAN_CONTRACT", &loan_contract);
+        Ok(())
     }
 
     pub fn update_fees(
         env: Env,
         transaction_fee: u32,
         gas_fee: u32,
-    ) {
-        let admin: Address = env.storage().instance().get(&"ADMIN").unwrap();
+    ) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
         admin.require_auth();
 
         let fee_config = FeeConfig {
@@ -163,64 +555,150 @@ impl GovernanceContract {
         };
 
         env.storage().instance().set(&"FEE_CONFIG", &fee_config);
+        Ok(())
     }
 
-    pub fn get_fees(env: Env) -> FeeConfig {
-        env.storage().instance().get(&"FEE_CONFIG").unwrap()
+    /// Renova o TTL da instância e dos claims dos empréstimos informados (qualquer conta pode chamar)
+    pub fn extend_ttl(env: Env, loan_ids: Vec<u64>) {
+        bump_instance(&env);
+        for loan_id in loan_ids.iter() {
+            bump_claim(&env, loan_id);
+        }
     }
 
-    pub fn get_protection_fund(env: Env) -> ProtectionFund {
-        env.storage().instance().get(&"PROTECTION_FUND").unwrap()
+    /// Pausa de emergência: `Originations` interrompe novas originações e `All`
+    /// qualquer movimentação nos contratos que consultam a governança. Os períodos em
+    /// `All` ficam registrados para que atrasos e carências não corram enquanto os
+    /// tomadores não podem pagar; só as `MAX_PAUSE_PERIODS` mais recentes são mantidas.
+    pub fn set_pause(env: Env, scope: PauseScope) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
+        admin.require_auth();
+
+        let now = env.ledger().timestamp();
+        let paused_since: Option<u64> = env.storage().instance().get(&"PAUSED_SINCE");
+        match (paused_since, scope == PauseScope::All) {
+            (None, true) => env.storage().instance().set(&"PAUSED_SINCE", &now),
+            (Some(start), false) => {
+                let mut periods: Vec<PausePeriod> = env.storage()
+                    .instance()
+                    .get(&"PAUSE_PERIODS")
+                    .unwrap_or(Vec::new(&env));
+                // Pausas encostadas na anterior são unidas a ela
+                match periods.last() {
+                    Some(mut last) if last.end >= start => {
+                        last.end = now;
+                        periods.set(periods.len() - 1, last);
+                    }
+                    _ => periods.push_back(PausePeriod { start, end: now }),
+                }
+                while periods.len() > MAX_PAUSE_PERIODS {
+                    periods.pop_front();
+                }
+                env.storage().instance().set(&"PAUSE_PERIODS", &periods);
+                env.storage().instance().remove(&"PAUSED_SINCE");
+            }
+            _ => {}
+        }
+
+        env.storage().instance().set(&"PAUSE", &scope);
+        env.events().publish((symbol_short!("paused"),), scope);
+        Ok(())
+    }
+
+    pub fn get_pause(env: Env) -> PauseScope {
+        bump_instance(&env);
+        env.storage().instance().get(&"PAUSE").unwrap_or(PauseScope::None)
+    }
+
+    /// Períodos de pausa `All`, em ordem; a pausa em curso termina no momento da consulta
+    pub fn get_pause_periods(env: Env) -> Vec<PausePeriod> {
+        bump_instance(&env);
+        let mut periods: Vec<PausePeriod> = env.storage()
+            .instance()
+            .get(&"PAUSE_PERIODS")
+            .unwrap_or(Vec::new(&env));
+        let paused_since: Option<u64> = env.storage().instance().get(&"PAUSED_SINCE");
+        if let Some(start) = paused_since {
+            periods.push_back(PausePeriod {
+                start,
+                end: env.ledger().timestamp(),
+            });
+        }
+        periods
+    }
+
+    pub fn get_fees(env: Env) -> Result<FeeConfig, GovernanceError> {
+        bump_instance(&env);
+        env.storage().instance().get(&"FEE_CONFIG").ok_or(GovernanceError::NotInitialized)
+    }
+
+    pub fn get_protection_fund(env: Env, asset: Address) -> Result<ProtectionFund, GovernanceError> {
+        bump_instance(&env);
+        load_fund(&env, &asset)
     }
 
+    /// Saca taxas acumuladas no ativo informado; o saldo do fundo de proteção desse
+    /// ativo não pode ser sacado
     pub fn withdraw_fees(
         env: Env,
         recipient: Address,
+        asset: Address,
         amount: i128,
-    ) {
-        let admin: Address = env.storage().instance().get(&"ADMIN").unwrap();
+    ) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
         admin.require_auth();
 
-        let fund: ProtectionFund = env.storage()
-            .instance()
-            .get(&"PROTECTION_FUND")
-            .unwrap();
+        let fund = load_fund(&env, &asset)?;
 
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(&env, &token_address);
+        let token_client = token::Client::new(&env, &asset);
         
         let contract_balance = token_client.balance(&env.current_contract_address());
         
         let available = contract_balance - fund.total_balance;
-        assert!(amount <= available, "Insufficient available funds");
+        if amount > available {
+            return Err(GovernanceError::InsufficientAvailableFunds);
+        }
 
         token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+        Ok(())
     }
 
     pub fn add_to_protection_fund(
         env: Env,
         from: Address,
+        asset: Address,
         amount: i128,
-    ) {
+    ) -> Result<(), GovernanceError> {
+        bump_instance(&env);
         from.require_auth();
 
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(&env, &token_address);
+        let token_client = token::Client::new(&env, &asset);
         token_client.transfer(&from, &env.current_contract_address(), &amount);
 
-        let mut fund: ProtectionFund = env.storage()
-            .instance()
-            .get(&"PROTECTION_FUND")
-            .unwrap();
+        let mut fund = load_fund(&env, &asset)?;
         
         fund.total_balance += amount;
-        env.storage().instance().set(&"PROTECTION_FUND", &fund);
+        save_fund(&env, &asset, &fund);
+        Ok(())
     }
 
-    pub fn transfer_admin(env: Env, new_admin: Address) {
-        let admin: Address = env.storage().instance().get(&"ADMIN").unwrap();
+    pub fn transfer_admin(env: Env, new_admin: Address) -> Result<(), GovernanceError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(GovernanceError::NotInitialized)?;
         admin.require_auth();
         
         env.storage().instance().set(&"ADMIN", &new_admin);
+        Ok(())
     }
 }
\ No newline at end of file
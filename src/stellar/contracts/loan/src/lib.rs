@@ -1,7 +1,32 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, symbol_short, token, Address, Env, String, Symbol, Vec};
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token, vec, xdr::ToXdr, Address,
+    BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
+};
 
 const DAY_IN_SECONDS: u64 = 86400;
+const MAX_INSTALLMENTS: u32 = 120;
+const RATE_SCALE: i128 = 1_000_000_000_000;
+// Limites usuais no Brasil: multa de até 2% (CDC art. 52, §1º) e juros de mora de até 1% a.m.
+const MAX_LATE_FINE_BPS: u32 = 200;
+const MAX_DEFAULT_INTEREST_BPS: u32 = 100;
+const DEFAULT_APPLICATION_TTL: u64 = 7 * DAY_IN_SECONDS;
+const DEFAULT_GRACE_PERIOD: u64 = 7 * DAY_IN_SECONDS;
+// Prazo para registrar o PIX de desembolso de um empréstimo pendente
+const PENDING_LOAN_EXPIRY: u64 = 7 * DAY_IN_SECONDS;
+const MAX_PAGE_SIZE: u32 = 50;
+const MAX_GUARANTORS: u32 = 3;
+// Layout de `Loan`, cards, aplicações e PIX: o 1 tinha parcela fixa e não tinha cotas,
+// o 2 não registrava o ativo em cada registro
+const SCHEMA_VERSION: u32 = 3;
+// TTL em ledgers (~5s cada): a instância é renovada a cada chamada e os dados de
+// empréstimos, cards e aplicações a cada escrita e leitura, mantendo ~120 dias de folga
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 120 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - 7 * DAY_IN_LEDGERS;
 
 // ==================== TYPES ====================
 
@@ -22,18 +47,41 @@ pub enum CardType {
     Request,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum AmortizationMethod {
+    Price,   // Tabela Price: parcelas fixas
+    Sac,     // SAC: amortização constante, parcelas decrescentes
+    Bullet,  // Juros mensais e todo o principal na última parcela
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct Installment {
+    pub number: u32,
+    pub due_date: u64,
+    pub principal: i128,
+    pub interest: i128,
+    pub amount: i128,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct Loan {
     pub id: u64,
     pub borrower: Address,
     pub lender: Address,
+    pub asset: Address,                  // Token em que o principal, as parcelas e as taxas são pagos
     pub amount: i128,
-    pub interest_rate: u32,
+    pub interest_rate: u32,              // Taxa mensal em basis points (ex: 500 = 5% a.m.)
+    pub amortization: AmortizationMethod,
     pub installments: u32,
-    pub installment_amount: i128,
+    pub installment_amount: i128,        // Valor da primeira parcela do cronograma
+    pub total_due: i128,                 // Soma de todas as parcelas do cronograma
     pub paid_installments: u32,
+    pub current_installment_paid: i128,  // Valor já pago da parcela em aberto
     pub total_paid: i128,
+    pub late_charges_paid: i128,         // Multa e juros de mora pagos ao credor
     pub status: LoanStatus,
     pub created_at: u64,
     pub next_payment_date: u64,
@@ -45,13 +93,16 @@ pub struct Loan {
 pub struct InvestmentCard {
     pub id: u64,
     pub investor: Address,
+    pub asset: Address,  // Token ofertado, da lista de ativos aceitos
     pub max_amount: i128,
     pub min_amount: i128,
     pub interest_rate: u32,
     pub max_installments: u32,
     pub target_risk_level: u32,
+    pub amortization: AmortizationMethod,
     pub is_active: bool,
     pub total_invested: i128,
+    pub escrowed_amount: i128,  // Capital depositado no contrato e ainda não emprestado
     pub created_at: u64,
 }
 
@@ -60,25 +111,121 @@ pub struct InvestmentCard {
 pub struct RequestCard {
     pub id: u64,
     pub borrower: Address,
+    pub asset: Address,  // Token pedido, da lista de ativos aceitos
     pub requested_amount: i128,
     pub desired_installments: u32,
     pub preferred_payment_dates: Vec<u64>,
+    pub amortization: AmortizationMethod,
+    pub interest_rate: u32,
+    pub funded_amount: i128,    // Soma das contribuições já comprometidas pelos credores
+    pub funding_deadline: u64,  // Após esse prazo, sem captação completa, as contribuições são devolvidas
     pub description: String,
     pub is_active: bool,
     pub is_funded: bool,
     pub created_at: u64,
 }
 
+#[derive(Clone)]
+#[contracttype]
+pub struct FundingCommitment {
+    pub lender: Address,
+    pub amount: i128,
+    pub fee: i128,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct LoanShare {
+    pub lender: Address,
+    pub amount: i128,   // Principal aportado; define a fração de cada parcela recebida
+}
+
+// Garantia exigida por um card, proporcional ao principal
+#[derive(Clone)]
+#[contracttype]
+pub struct CollateralTerms {
+    pub asset: Address,  // Deve ser o ativo do card
+    pub ratio_bps: u32,  // Ex.: 15000 = garantia de 150% do principal
+}
+
+// Garantia travada no contrato pelo tomador, da aplicação (ou do card de solicitação)
+// até a quitação, o cancelamento ou a inadimplência do empréstimo
+#[derive(Clone)]
+#[contracttype]
+pub struct Collateral {
+    pub owner: Address,
+    pub asset: Address,
+    pub amount: i128,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum ProposalStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RestructuringProposal {
+    pub loan_id: u64,
+    pub interest_rate: u32,
+    pub installments: u32,      // Novas parcelas para o saldo remanescente
+    pub payment_dates: Vec<u64>,
+    pub proposed_at: u64,
+    pub status: ProposalStatus,
+}
+
+// Condições substituídas numa renegociação, mantidas para auditoria
+#[derive(Clone)]
+#[contracttype]
+pub struct LoanTerms {
+    pub interest_rate: u32,
+    pub installments: u32,
+    pub total_due: i128,
+    pub schedule: Vec<Installment>,
+    pub replaced_at: u64,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct PositionListing {
+    pub loan_id: u64,
+    pub seller: Address,
+    pub price: i128,
+    pub listed_at: u64,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct OwnershipTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub amount: i128,   // Principal da posição transferida
+    pub price: i128,
+    pub timestamp: u64,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct Payment {
     pub loan_id: u64,
     pub installment_number: u32,
-    pub amount: i128,
+    pub amount: i128,          // Valor abatido da parcela
+    pub late_fee: i128,        // Multa por atraso
+    pub late_interest: i128,   // Juros de mora pro rata die
     pub paid_at: u64,
     pub was_on_time: bool,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct LateFeeConfig {
+    pub fine_bps: u32,               // Multa única sobre o valor em atraso
+    pub monthly_interest_bps: u32,   // Juros de mora mensais, cobrados por dia de atraso
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct LoanApplication {
@@ -88,15 +235,171 @@ pub struct LoanApplication {
     pub applicant: Address,
     pub amount: i128,
     pub status: ApplicationStatus,
+    pub reason_code: Option<u32>, // Motivo informado pelo investidor na recusa
     pub created_at: u64,
+    pub expires_at: u64,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[contracttype]
 pub enum ApplicationStatus {
     Pending,
     Approved,
     Rejected,
+    Withdrawn,
+    Expired,
+}
+
+// ==================== ERRORS ====================
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum LoanError {
+    NotInitialized = 1,
+    CardNotFound = 2,
+    CardNotActive = 3,
+    CardAlreadyFunded = 4,
+    AmountOutOfRange = 5,
+    InvalidAmount = 6,
+    InstallmentsOutOfRange = 7,
+    InsufficientEscrow = 8,
+    CreditScoreTooLow = 9,
+    CreditLimitExceeded = 10,
+    ApplicationNotFound = 11,
+    ApplicationNotPending = 12,
+    ApplicationExpired = 13,
+    ApplicationNotExpired = 14,
+    InvalidApplicationTtl = 15,
+    InvalidFundingDeadline = 16,
+    FundingDeadlinePassed = 17,
+    FundingDeadlineNotReached = 18,
+    LoanNotFound = 19,
+    LoanNotActive = 20,
+    AmountExceedsOutstanding = 21,
+    GracePeriodNotExpired = 22,
+    LateFeeAboveCap = 23,
+    ListingNotFound = 24,
+    PositionNotFound = 25,
+    BuyerIsSeller = 26,
+    ProposalNotFound = 27,
+    ProposalNotPending = 28,
+    ProposalAlreadyPending = 29,
+    ScheduleOverflow = 30,
+    PixPaymentNotFound = 31,
+    ContractPaused = 32,
+    FeeConfigChanged = 33,
+    DuplicatePixPayment = 34,
+    DuplicatePixOrder = 35,
+    InvalidPixTransition = 36,
+    UnknownPixOracle = 37,
+    PixAttestationMismatch = 38,
+    LoanNotPending = 39,
+    AssetNotAllowed = 40,
+    InvalidCollateralTerms = 41,
+    InvalidGuarantor = 42,
+    LoanNotDefaulted = 43,
+    NoGuaranteeDue = 44,
+    InvalidGracePeriod = 45,
+    InsufficientAllowance = 46,
+    PendingLoanNotExpired = 47,
+    PayoutAlreadyRegistered = 48,
+    AlreadyInitialized = 49,
+    DefaultNotRecorded = 50,
+}
+
+// ==================== EXTERNAL CONTRACTS ====================
+
+// Espelho dos tipos do contrato de credit score usados nas chamadas entre contratos
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct CreditScore {
+    pub user: Address,
+    pub score: u32,
+    pub risk_level: RiskLevel,
+    pub on_chain_score: u32,
+    pub off_chain_score: u32,
+    pub payment_history: u32,
+    pub total_transactions: u32,
+    pub default_count: u32,
+    pub last_updated: u64,
+}
+
+#[contractclient(name = "CreditScoreClient")]
+pub trait CreditScoreInterface {
+    fn get_score(env: Env, user: Address) -> Option<CreditScore>;
+    fn can_borrow(env: Env, user: Address, amount: i128) -> bool;
+    fn record_payment(env: Env, user: Address, loan_id: u64, amount: i128, on_time: bool);
+    fn record_default(env: Env, user: Address, loan_id: u64, outstanding_amount: i128);
+    fn record_renegotiation(env: Env, user: Address, loan_id: u64);
+    fn record_guarantee(env: Env, guarantor: Address, loan_id: u64, amount: i128);
+}
+
+// Espelho do escopo de pausa do contrato de governança
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum PauseScope {
+    None,
+    Originations,
+    All,
+}
+
+// Espelho de um período de pausa `All` registrado pela governança
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct PausePeriod {
+    pub start: u64,
+    pub end: u64,
+}
+
+// Espelho da configuração de taxas da governança (basis points sobre o valor do empréstimo)
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct FeeConfig {
+    pub transaction_fee: u32,
+    pub gas_fee: u32,
+    pub last_updated: u64,
+}
+
+// Espelho do claim aberto no fundo de proteção da governança
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum ClaimStatus {
+    Open,
+    Paid,
+    Closed,
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct ProtectionClaim {
+    pub loan_id: u64,
+    pub asset: Address,
+    pub principal: i128,
+    pub payout: i128,
+    pub status: ClaimStatus,
+    pub opened_at: u64,
+    pub updated_at: u64,
+}
+
+#[contractclient(name = "GovernanceClient")]
+pub trait GovernanceInterface {
+    fn get_pause(env: Env) -> PauseScope;
+    fn get_pause_periods(env: Env) -> Vec<PausePeriod>;
+    fn get_fees(env: Env) -> FeeConfig;
+    fn collect_transaction_fee(env: Env, from: Address, asset: Address, amount: i128) -> i128;
+    fn collect_gas_fee(env: Env, from: Address, asset: Address, transaction_amount: i128) -> i128;
+    fn open_claim(env: Env, loan_id: u64, asset: Address, principal: i128);
+    fn reduce_claim(env: Env, loan_id: u64, recovered: i128);
+    fn get_claim(env: Env, loan_id: u64) -> Option<ProtectionClaim>;
 }
 
 // ==================== EVENTS ====================
@@ -104,7 +407,44 @@ pub enum ApplicationStatus {
 pub const LOAN_CREATED: Symbol = symbol_short!("created");
 pub const PAYMENT_MADE: Symbol = symbol_short!("payment");
 pub const LOAN_COMPLETED: Symbol = symbol_short!("complete");
+pub const LOAN_ACTIVATED: Symbol = symbol_short!("activated");
+pub const LOAN_CANCELLED: Symbol = symbol_short!("cancelled");
+pub const LOAN_DEFAULTED: Symbol = symbol_short!("defaulted");
+pub const PAYMENT_OVERDUE: Symbol = symbol_short!("overdue");
+pub const DEFAULT_FAILED: Symbol = symbol_short!("dflt_fail");
 pub const CARD_CREATED: Symbol = symbol_short!("card_new");
+pub const FUNDING_COMMITTED: Symbol = symbol_short!("committed");
+pub const FUNDING_REFUNDED: Symbol = symbol_short!("refunded");
+pub const POSITION_LISTED: Symbol = symbol_short!("listed");
+pub const POSITION_UNLISTED: Symbol = symbol_short!("unlisted");
+pub const POSITION_SOLD: Symbol = symbol_short!("pos_sold");
+pub const RESTRUCTURING_PROPOSED: Symbol = symbol_short!("restr_new");
+pub const RESTRUCTURING_APPROVED: Symbol = symbol_short!("restr_apv");
+pub const RESTRUCTURING_ACCEPTED: Symbol = symbol_short!("restr_ok");
+pub const RESTRUCTURING_REJECTED: Symbol = symbol_short!("restr_no");
+pub const APPLICATION_SUBMITTED: Symbol = symbol_short!("app_new");
+pub const APPLICATION_APPROVED: Symbol = symbol_short!("app_ok");
+pub const APPLICATION_REJECTED: Symbol = symbol_short!("app_rej");
+pub const APPLICATION_WITHDRAWN: Symbol = symbol_short!("app_wdrw");
+pub const APPLICATION_EXPIRED: Symbol = symbol_short!("app_exp");
+pub const CONTRACT_UPGRADED: Symbol = symbol_short!("upgraded");
+pub const SCHEMA_MIGRATED: Symbol = symbol_short!("migrated");
+pub const PIX_REGISTERED: Symbol = symbol_short!("pix_new");
+pub const PIX_STATUS_CHANGED: Symbol = symbol_short!("pix_upd");
+pub const PIX_ATTESTED: Symbol = symbol_short!("pix_att");
+pub const PIX_ORACLE_ADDED: Symbol = symbol_short!("orcl_add");
+pub const PIX_ORACLE_REMOVED: Symbol = symbol_short!("orcl_del");
+pub const ASSET_ADDED: Symbol = symbol_short!("asset_add");
+pub const ASSET_REMOVED: Symbol = symbol_short!("asset_del");
+pub const COLLATERAL_LOCKED: Symbol = symbol_short!("coll_lock");
+pub const COLLATERAL_RELEASED: Symbol = symbol_short!("coll_rel");
+pub const COLLATERAL_SEIZED: Symbol = symbol_short!("coll_seiz");
+pub const GUARANTOR_ADDED: Symbol = symbol_short!("guar_add");
+pub const GUARANTEE_CALLED: Symbol = symbol_short!("guar_call");
+pub const AUTO_DEBIT_ENABLED: Symbol = symbol_short!("adb_on");
+pub const AUTO_DEBIT_DISABLED: Symbol = symbol_short!("adb_off");
+pub const AUTO_DEBIT_COLLECTED: Symbol = symbol_short!("adb_paid");
+pub const AUTO_DEBIT_FAILED: Symbol = symbol_short!("adb_fail");
 
 #[contracttype]
 #[derive(Clone)]
@@ -141,11 +481,65 @@ pub struct CardCreatedEvent {
     pub is_investment_card: bool,
 }
 
+#[contracttype]
+#[derive(Clone)]
+pub struct FundingCommittedEvent {
+    pub card_id: u64,
+    pub lender: Address,
+    pub amount: i128,
+    pub funded_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct FundingRefundedEvent {
+    pub card_id: u64,
+    pub lender: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct PositionListedEvent {
+    pub loan_id: u64,
+    pub seller: Address,
+    pub price: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct PositionSoldEvent {
+    pub loan_id: u64,
+    pub seller: Address,
+    pub buyer: Address,
+    pub amount: i128,
+    pub price: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct RestructuringEvent {
+    pub loan_id: u64,
+    pub interest_rate: u32,
+    pub installments: u32,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct ApplicationEvent {
+    pub app_id: u64,
+    pub card_id: u64,
+    pub applicant: Address,
+    pub status: ApplicationStatus,
+    pub reason_code: Option<u32>,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct PixPayment {
     pub payment_id: String,  // ID from PIX API
     pub order_id: String,    // id_pedido from PIX API  
+    pub asset: Address,      // Token do empréstimo relacionado
     pub amount: i128,
     pub status: PixStatus,
     pub created_at: u64,
@@ -154,7 +548,7 @@ pub struct PixPayment {
     pub related_loan_id: u64,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[contracttype] 
 pub enum PixStatus {
     Pending,
@@ -173,6 +567,43 @@ pub enum PixType {
     Repayment,
 }
 
+// Entrada do histórico de status de um PIX, usado na conciliação com o provedor
+#[derive(Clone)]
+#[contracttype]
+pub struct PixStatusChange {
+    pub status: PixStatus,
+    pub timestamp: u64,
+}
+
+// Status de um PIX assinado por um oráculo; a assinatura ed25519 cobre o XDR do par
+// (endereço do contrato de empréstimo, atestado)
+#[derive(Clone)]
+#[contracttype]
+pub struct PixAttestation {
+    pub payment_id: String,
+    pub order_id: String,
+    pub amount: i128,
+    pub status: PixStatus,
+    pub timestamp: u64,  // Horário do evento no provedor; só repassado no evento `pix_att`
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct PixAttestedEvent {
+    pub payment_id: String,
+    pub status: PixStatus,
+    pub oracle: BytesN<32>,
+    pub attested_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct PixStatusEvent {
+    pub payment_id: String,
+    pub order_id: String,
+    pub status: PixStatus,
+}
+
 fn emit_loan_created(env: &Env, loan_id: u64, borrower: Address, lender: Address, amount: i128) {
     env.events().publish(
         (LOAN_CREATED,),
@@ -220,82 +651,1520 @@ fn emit_card_created(env: &Env, card_id: u64, creator: Address, is_investment_ca
     );
 }
 
-// ==================== CONTRACT ====================
-
-#[contract]
-pub struct LoanContract;
+fn emit_funding_committed(env: &Env, card_id: u64, lender: Address, amount: i128, funded_amount: i128) {
+    env.events().publish(
+        (FUNDING_COMMITTED,),
+        FundingCommittedEvent {
+            card_id,
+            lender,
+            amount,
+            funded_amount,
+        },
+    );
+}
 
-#[contractimpl]
-impl LoanContract {
-    pub fn initialize(
-        env: Env,
-        admin: Address,
-        token: Address,
-        governance_contract: Address,
-        credit_score_contract: Address,
-    ) {
-        admin.require_auth();
-        
-        env.storage().instance().set(&"ADMIN", &admin);
-        env.storage().instance().set(&"TOKEN", &token);
-        env.storage().instance().set(&"GOVERNANCE", &governance_contract);
-        env.storage().instance().set(&"CREDIT_SCORE", &credit_score_contract);
-        env.storage().instance().set(&"NEXT_LOAN_ID", &1u64);
-        env.storage().instance().set(&"NEXT_CARD_ID", &1u64);
-        env.storage().instance().set(&"NEXT_APP_ID", &1u64);
-    }
+fn emit_funding_refunded(env: &Env, card_id: u64, lender: Address, amount: i128) {
+    env.events().publish(
+        (FUNDING_REFUNDED,),
+        FundingRefundedEvent {
+            card_id,
+            lender,
+            amount,
+        },
+    );
+}
 
-    pub fn create_investment_card(
-        env: Env,
-        investor: Address,
-        max_amount: i128,
-        min_amount: i128,
-        interest_rate: u32,
-        max_installments: u32,
-        target_risk_level: u32,
-    ) -> u64 {
-        investor.require_auth();
+fn emit_position_listed(env: &Env, loan_id: u64, seller: Address, price: i128) {
+    env.events().publish(
+        (POSITION_LISTED,),
+        PositionListedEvent {
+            loan_id,
+            seller,
+            price,
+        },
+    );
+}
 
-        let card_id: u64 = env.storage().instance().get(&"NEXT_CARD_ID").unwrap();
-        
-        let card = InvestmentCard {
-            id: card_id,
-            investor: investor.clone(),
-            max_amount,
-            min_amount,
-            interest_rate,
-            max_installments,
-            target_risk_level,
-            is_active: true,
-            total_invested: 0,
-            created_at: env.ledger().timestamp(),
-        };
+fn emit_position_unlisted(env: &Env, loan_id: u64, seller: Address) {
+    env.events().publish((POSITION_UNLISTED,), (loan_id, seller));
+}
 
-        env.storage().persistent().set(&("INV_CARD", card_id), &card);
-        env.storage().instance().set(&"NEXT_CARD_ID", &(card_id + 1));
+fn emit_position_sold(env: &Env, loan_id: u64, seller: Address, buyer: Address, amount: i128, price: i128) {
+    env.events().publish(
+        (POSITION_SOLD,),
+        PositionSoldEvent {
+            loan_id,
+            seller,
+            buyer,
+            amount,
+            price,
+        },
+    );
+}
 
-        emit_card_created(&env, card_id, investor, true);
-        card_id
-    }
+fn emit_restructuring(env: &Env, topic: Symbol, proposal: &RestructuringProposal) {
+    env.events().publish(
+        (topic,),
+        RestructuringEvent {
+            loan_id: proposal.loan_id,
+            interest_rate: proposal.interest_rate,
+            installments: proposal.installments,
+        },
+    );
+}
 
-    pub fn create_request_card(
-        env: Env,
-        borrower: Address,
-        requested_amount: i128,
-        desired_installments: u32,
-        preferred_payment_dates: Vec<u64>,
-        description: String,
-    ) -> u64 {
-        borrower.require_auth();
+fn emit_pix_status(env: &Env, topic: Symbol, payment: &PixPayment) {
+    env.events().publish(
+        (topic,),
+        PixStatusEvent {
+            payment_id: payment.payment_id.clone(),
+            order_id: payment.order_id.clone(),
+            status: payment.status.clone(),
+        },
+    );
+}
 
-        let card_id: u64 = env.storage().instance().get(&"NEXT_CARD_ID").unwrap();
-        
-        let card = RequestCard {
+fn emit_application(env: &Env, topic: Symbol, application: &LoanApplication) {
+    env.events().publish(
+        (topic,),
+        ApplicationEvent {
+            app_id: application.id,
+            card_id: application.card_id,
+            applicant: application.applicant.clone(),
+            status: application.status.clone(),
+            reason_code: application.reason_code,
+        },
+    );
+}
+
+fn fee_config(env: &Env) -> Result<FeeConfig, LoanError> {
+    let governance: Address = env.storage()
+        .instance()
+        .get(&"GOVERNANCE")
+        .ok_or(LoanError::NotInitialized)?;
+    Ok(GovernanceClient::new(env, &governance).get_fees())
+}
+
+/// Taxa de transação mais a contribuição ao fundo de proteção, com o mesmo
+/// arredondamento usado pela governança na cobrança
+fn origination_fee(config: &FeeConfig, amount: i128) -> i128 {
+    amount * config.transaction_fee as i128 / 10000 + amount * config.gas_fee as i128 / 10000
+}
+
+/// Paga as taxas de originação de `amount` com o saldo em custódia por meio das
+/// entradas da governança: a taxa de transação fica no caixa da governança e a de
+/// gás vai para o fundo de proteção do ativo. Retorna o total cobrado.
+fn collect_origination_fees(env: &Env, asset: &Address, amount: i128) -> Result<i128, LoanError> {
+    let governance: Address = env.storage()
+        .instance()
+        .get(&"GOVERNANCE")
+        .ok_or(LoanError::NotInitialized)?;
+    let client = GovernanceClient::new(env, &governance);
+    let config = client.get_fees();
+    let contract = env.current_contract_address();
+
+    let transaction_fee = amount * config.transaction_fee as i128 / 10000;
+    authorize_fee_transfer(env, asset, &governance, transaction_fee);
+    client.collect_transaction_fee(&contract, asset, &amount);
+
+    let gas_fee = amount * config.gas_fee as i128 / 10000;
+    authorize_fee_transfer(env, asset, &governance, gas_fee);
+    client.collect_gas_fee(&contract, asset, &amount);
+
+    Ok(transaction_fee + gas_fee)
+}
+
+/// Cobra as taxas de originação reservadas para um empréstimo pendente, uma vez por
+/// credor, e devolve a cada um a sobra da reserva. Empréstimos criados antes da
+/// reserva já tiveram as taxas cobradas e não têm `LOAN_FEES`.
+fn charge_reserved_fees(env: &Env, loan: &Loan) -> Result<(), LoanError> {
+    let reserved: Vec<FundingCommitment> = match env.storage().persistent().get(&("LOAN_FEES", loan.id)) {
+        Some(reserved) => reserved,
+        None => return Ok(()),
+    };
+
+    // Um aumento das taxas após a originação não pode ser coberto com a custódia de outros
+    let config = fee_config(env)?;
+    for commitment in reserved.iter() {
+        if origination_fee(&config, commitment.amount) > commitment.fee {
+            return Err(LoanError::FeeConfigChanged);
+        }
+    }
+
+    let token_client = token::Client::new(env, &loan.asset);
+    for commitment in reserved.iter() {
+        let charged = collect_origination_fees(env, &loan.asset, commitment.amount)?;
+        let surplus = commitment.fee - charged;
+        if surplus > 0 {
+            token_client.transfer(&env.current_contract_address(), &commitment.lender, &surplus);
+        }
+    }
+    env.storage().persistent().remove(&("LOAN_FEES", loan.id));
+    Ok(())
+}
+
+/// Devolve a cada credor a taxa reservada de um empréstimo que não chegou a ser ativado
+fn refund_reserved_fees(env: &Env, loan: &Loan) {
+    let reserved: Vec<FundingCommitment> = match env.storage().persistent().get(&("LOAN_FEES", loan.id)) {
+        Some(reserved) => reserved,
+        None => return,
+    };
+
+    let token_client = token::Client::new(env, &loan.asset);
+    for commitment in reserved.iter() {
+        if commitment.fee > 0 {
+            token_client.transfer(&env.current_contract_address(), &commitment.lender, &commitment.fee);
+        }
+    }
+    env.storage().persistent().remove(&("LOAN_FEES", loan.id));
+}
+
+/// A governança transfere a taxa em nome do contrato; como essa transferência não é
+/// chamada diretamente pelo contrato, a autorização precisa ser concedida antes
+fn authorize_fee_transfer(env: &Env, token: &Address, governance: &Address, fee: i128) {
+    env.authorize_as_current_contract(vec![
+        env,
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: token.clone(),
+                fn_name: Symbol::new(env, "transfer"),
+                args: (env.current_contract_address(), governance.clone(), fee).into_val(env),
+            },
+            sub_invocations: Vec::new(env),
+        }),
+    ]);
+}
+
+/// Cronograma de um empréstimo já ativado
+fn load_schedule(env: &Env, loan_id: u64) -> Result<Vec<Installment>, LoanError> {
+    env.storage()
+        .persistent()
+        .get(&("SCHEDULE", loan_id))
+        .ok_or(LoanError::LoanNotFound)
+}
+
+/// Cotas dos credores de um empréstimo
+fn load_shares(env: &Env, loan_id: u64) -> Result<Vec<LoanShare>, LoanError> {
+    env.storage()
+        .persistent()
+        .get(&("SHARES", loan_id))
+        .ok_or(LoanError::LoanNotFound)
+}
+
+fn outstanding_balance(loan: &Loan) -> i128 {
+    loan.total_due - loan.total_paid
+}
+
+/// Principal ainda devido. Pagamentos parciais da parcela em aberto quitam
+/// primeiro os juros e depois o principal dessa parcela.
+fn outstanding_principal(env: &Env, loan: &Loan) -> Result<i128, LoanError> {
+    let schedule = load_schedule(env, loan.id)?;
+
+    let mut principal = 0;
+    for index in loan.paid_installments..loan.installments {
+        principal += schedule.get(index).unwrap().principal;
+    }
+
+    if let Some(current) = schedule.get(loan.paid_installments) {
+        principal -= (loan.current_installment_paid - current.interest).max(0);
+    }
+    Ok(principal)
+}
+
+/// Juros de parcelas já vencidas que ainda não foram pagos
+fn overdue_interest(env: &Env, loan: &Loan) -> Result<i128, LoanError> {
+    let schedule = load_schedule(env, loan.id)?;
+    let current_time = env.ledger().timestamp();
+
+    let mut interest = 0;
+    for index in loan.paid_installments..loan.installments {
+        let installment = schedule.get(index).unwrap();
+        if installment.due_date > current_time {
+            break;
+        }
+        interest += installment.interest;
+        if index == loan.paid_installments {
+            interest -= loan.current_installment_paid.min(installment.interest);
+        }
+    }
+    Ok(interest)
+}
+
+/// Credor de referência do empréstimo: o maior aporte (o primeiro, em caso de empate)
+fn lead_lender(shares: &Vec<LoanShare>) -> Address {
+    let mut lead = shares.get(0).unwrap();
+    for share in shares.iter() {
+        if share.amount > lead.amount {
+            lead = share;
+        }
+    }
+    lead.lender
+}
+
+/// Repassa `amount`, já em posse do contrato, aos credores proporcionalmente ao
+/// principal de cada um. O resto do arredondamento vai para o último credor.
+fn distribute_to_lenders(
+    env: &Env,
+    token_client: &token::Client,
+    loan: &Loan,
+    amount: i128,
+) -> Result<(), LoanError> {
+    let shares = load_shares(env, loan.id)?;
+
+    let mut distributed = 0;
+    for (index, share) in shares.iter().enumerate() {
+        let value = if index as u32 == shares.len() - 1 {
+            amount - distributed
+        } else {
+            amount * share.amount / loan.amount
+        };
+
+        if value > 0 {
+            token_client.transfer(&env.current_contract_address(), &share.lender, &value);
+        }
+        distributed += value;
+    }
+    Ok(())
+}
+
+/// Rejeita a operação se a governança pausou o contrato. Originações (novos cards,
+/// aplicações e empréstimos) param em qualquer pausa; as demais apenas em `All`,
+/// para que os tomadores continuem pagando durante uma pausa de originações.
+fn ensure_not_paused(env: &Env, is_origination: bool) -> Result<(), LoanError> {
+    let governance: Address = env.storage()
+        .instance()
+        .get(&"GOVERNANCE")
+        .ok_or(LoanError::NotInitialized)?;
+
+    match GovernanceClient::new(env, &governance).get_pause() {
+        PauseScope::None => Ok(()),
+        PauseScope::Originations if !is_origination => Ok(()),
+        _ => Err(LoanError::ContractPaused),
+    }
+}
+
+/// Consulta o contrato de credit score e rejeita tomadores abaixo do score
+/// mínimo do card ou acima do limite permitido para o seu nível de risco.
+fn check_borrower_eligibility(
+    env: &Env,
+    borrower: &Address,
+    amount: i128,
+    min_score: u32,
+) -> Result<(), LoanError> {
+    let credit_score: Address = env.storage()
+        .instance()
+        .get(&"CREDIT_SCORE")
+        .ok_or(LoanError::NotInitialized)?;
+    let score_client = CreditScoreClient::new(env, &credit_score);
+
+    if min_score > 0 {
+        let score = score_client.get_score(borrower).map(|s| s.score).unwrap_or(0);
+        if score < min_score {
+            return Err(LoanError::CreditScoreTooLow);
+        }
+    }
+
+    if !score_client.can_borrow(borrower, &amount) {
+        return Err(LoanError::CreditLimitExceeded);
+    }
+    Ok(())
+}
+
+// ==================== ASSETS ====================
+
+/// Token informado no `initialize`; registros anteriores aos ativos por card usam ele
+fn default_asset(env: &Env) -> Result<Address, LoanError> {
+    env.storage().instance().get(&"TOKEN").ok_or(LoanError::NotInitialized)
+}
+
+fn allowed_assets(env: &Env) -> Result<Vec<Address>, LoanError> {
+    match env.storage().instance().get(&"ASSETS") {
+        Some(assets) => Ok(assets),
+        None => Ok(vec![env, default_asset(env)?]),
+    }
+}
+
+fn ensure_asset_allowed(env: &Env, asset: &Address) -> Result<(), LoanError> {
+    if !allowed_assets(env)?.contains(asset) {
+        return Err(LoanError::AssetNotAllowed);
+    }
+    Ok(())
+}
+
+// ==================== TTL ====================
+
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn bump_persistent<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    if env.storage().persistent().has(key) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+/// Lê uma entrada persistente renovando o TTL dela, para que dados consultados
+/// com frequência não sejam arquivados entre escritas
+fn read_persistent<K, V>(env: &Env, key: &K) -> Option<V>
+where
+    K: IntoVal<Env, Val>,
+    V: TryFromVal<Env, Val>,
+{
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+    value
+}
+
+/// Renova todas as entradas de um empréstimo (dados, cronograma, posições e históricos)
+fn bump_loan(env: &Env, loan_id: u64) {
+    bump_persistent(env, &("LOAN", loan_id));
+    bump_persistent(env, &("SCHEDULE", loan_id));
+    bump_persistent(env, &("SHARES", loan_id));
+    bump_persistent(env, &("PAYMENTS", loan_id));
+    bump_persistent(env, &("OWNERSHIP", loan_id));
+    bump_persistent(env, &("RESTRUCT", loan_id));
+    bump_persistent(env, &("RESTR_APPROVALS", loan_id));
+    bump_persistent(env, &("TERMS_HISTORY", loan_id));
+    bump_persistent(env, &("LOAN_PAYOUT", loan_id));
+    bump_persistent(env, &("LOAN_FEES", loan_id));
+    bump_persistent(env, &("LOAN_CARD", loan_id));
+    bump_persistent(env, &("COLLATERAL", loan_id));
+    bump_persistent(env, &("GUARANTORS", loan_id));
+    bump_persistent(env, &("AUTO_DEBIT", loan_id));
+    bump_persistent(env, &("OVERDUE_NOTICE", loan_id));
+}
+
+fn bump_card(env: &Env, card_id: u64) {
+    bump_persistent(env, &("INV_CARD", card_id));
+    bump_persistent(env, &("REQ_CARD", card_id));
+    bump_persistent(env, &("REQ_FUNDS", card_id));
+    bump_persistent(env, &("CARD_APPS", card_id));
+    bump_persistent(env, &("COLLATERAL_TERMS", card_id));
+    bump_persistent(env, &("REQ_COLLATERAL", card_id));
+    bump_persistent(env, &("REQ_GUARANTORS", card_id));
+}
+
+// ==================== INDEXES ====================
+
+// Listas de IDs mantidas a cada criação ou mudança de estado, usadas nas consultas paginadas
+
+fn index_add<K: IntoVal<Env, Val>>(env: &Env, key: &K, id: u64) {
+    let mut ids: Vec<u64> = env.storage().persistent().get(key).unwrap_or(Vec::new(env));
+    if !ids.contains(id) {
+        ids.push_back(id);
+        env.storage().persistent().set(key, &ids);
+    }
+    bump_persistent(env, key);
+}
+
+fn index_remove<K: IntoVal<Env, Val>>(env: &Env, key: &K, id: u64) {
+    let mut ids: Vec<u64> = env.storage().persistent().get(key).unwrap_or(Vec::new(env));
+    if let Some(index) = ids.first_index_of(id) {
+        ids.remove(index);
+        env.storage().persistent().set(key, &ids);
+    }
+    bump_persistent(env, key);
+}
+
+fn index_get<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> Vec<u64> {
+    read_persistent(env, key).unwrap_or(Vec::new(env))
+}
+
+/// Altera o status do empréstimo mantendo o índice por status
+fn set_loan_status(env: &Env, loan: &mut Loan, status: LoanStatus) {
+    index_remove(env, &("STATUS_LOANS", loan.status.clone()), loan.id);
+    index_add(env, &("STATUS_LOANS", status.clone()), loan.id);
+    loan.status = status;
+}
+
+fn index_new_loan(env: &Env, loan: &Loan, shares: &Vec<LoanShare>) {
+    index_add(env, &("BORROWER_LOANS", loan.borrower.clone()), loan.id);
+    for share in shares.iter() {
+        index_add(env, &("LENDER_LOANS", share.lender), loan.id);
+    }
+    index_add(env, &("STATUS_LOANS", loan.status.clone()), loan.id);
+}
+
+/// Página `start..start + limit` dos empréstimos de `ids`, opcionalmente filtrados por status
+fn paginate_loans(
+    env: &Env,
+    ids: Vec<u64>,
+    status: Option<LoanStatus>,
+    start: u32,
+    limit: u32,
+) -> Vec<Loan> {
+    let mut page = Vec::new(env);
+    let mut skipped = 0;
+    for id in ids.iter() {
+        if page.len() >= limit.min(MAX_PAGE_SIZE) {
+            break;
+        }
+        // IDs cujo registro expirou são ignorados em vez de abortar a listagem
+        let loan: Loan = match read_persistent(env, &("LOAN", id)) {
+            Some(loan) => loan,
+            None => continue,
+        };
+        if status.as_ref().is_some_and(|status| *status != loan.status) {
+            continue;
+        }
+        if skipped < start {
+            skipped += 1;
+            continue;
+        }
+        page.push_back(loan);
+    }
+    page
+}
+
+// ==================== AMORTIZATION ====================
+
+fn mul_div_round(a: i128, b: i128, divisor: i128) -> Result<i128, LoanError> {
+    let product = a.checked_mul(b).ok_or(LoanError::ScheduleOverflow)?;
+    Ok((product + divisor / 2) / divisor)
+}
+
+/// Parcela da tabela Price: PMT = P * i / (1 - (1 + i)^-n), com `rate` escalado por RATE_SCALE
+fn price_installment(amount: i128, rate: i128, installments: u32) -> Result<i128, LoanError> {
+    if rate == 0 {
+        return Ok(amount / installments as i128);
+    }
+
+    let mut factor = RATE_SCALE;
+    for _ in 0..installments {
+        factor = mul_div_round(factor, RATE_SCALE + rate, RATE_SCALE)?;
+    }
+
+    let annuity = mul_div_round(rate, factor, factor - RATE_SCALE)?;
+    mul_div_round(amount, annuity, RATE_SCALE)
+}
+
+/// Gera o cronograma de parcelas. Os juros incidem sobre o saldo devedor a cada
+/// período mensal e qualquer diferença de arredondamento vai para a última parcela.
+fn build_schedule(
+    env: &Env,
+    amount: i128,
+    interest_rate: u32,
+    amortization: &AmortizationMethod,
+    installments: u32,
+    payment_dates: &Vec<u64>,
+    start: u64,
+) -> Result<Vec<Installment>, LoanError> {
+    if amount <= 0 {
+        return Err(LoanError::InvalidAmount);
+    }
+    if installments == 0 || installments > MAX_INSTALLMENTS {
+        return Err(LoanError::InstallmentsOutOfRange);
+    }
+
+    let rate = interest_rate as i128 * RATE_SCALE / 10000;
+    let fixed_payment = price_installment(amount, rate, installments)?;
+    let constant_amortization = amount / installments as i128;
+
+    let mut schedule = Vec::new(env);
+    let mut balance = amount;
+
+    for number in 1..=installments {
+        let interest = mul_div_round(balance, rate, RATE_SCALE)?;
+        let principal = if number == installments {
+            balance
+        } else {
+            match amortization {
+                AmortizationMethod::Price => fixed_payment - interest,
+                AmortizationMethod::Sac => constant_amortization,
+                AmortizationMethod::Bullet => 0,
+            }
+        };
+
+        let due_date = payment_dates
+            .get(number - 1)
+            .unwrap_or(start + number as u64 * 30 * DAY_IN_SECONDS);
+
+        schedule.push_back(Installment {
+            number,
+            due_date,
+            principal,
+            interest,
+            amount: principal + interest,
+        });
+
+        balance -= principal;
+    }
+
+    Ok(schedule)
+}
+
+// ==================== LATE FEES ====================
+
+/// Períodos em que a governança pausou tudo; durante eles o tomador não pode pagar
+fn pause_periods(env: &Env) -> Result<Vec<PausePeriod>, LoanError> {
+    let governance: Address = env.storage()
+        .instance()
+        .get(&"GOVERNANCE")
+        .ok_or(LoanError::NotInitialized)?;
+    Ok(GovernanceClient::new(env, &governance).get_pause_periods())
+}
+
+/// `current_time` descontado o tempo pausado desde `since`, para que atrasos e
+/// carências contem apenas o tempo em que o pagamento era possível
+fn unpaused_time(periods: &Vec<PausePeriod>, since: u64, current_time: u64) -> u64 {
+    let mut paused = 0;
+    for period in periods.iter() {
+        let start = period.start.max(since);
+        let end = period.end.min(current_time);
+        if end > start {
+            paused += end - start;
+        }
+    }
+    current_time - paused
+}
+
+/// Dias de atraso contados a partir do vencimento, com um dia de tolerância
+fn days_overdue(due_date: u64, current_time: u64) -> u64 {
+    if current_time <= due_date + DAY_IN_SECONDS {
+        return 0;
+    }
+    (current_time - due_date).div_ceil(DAY_IN_SECONDS)
+}
+
+fn late_charges(config: &LateFeeConfig, overdue_amount: i128, days_late: u64) -> (i128, i128) {
+    if days_late == 0 {
+        return (0, 0);
+    }
+    let fine = overdue_amount * config.fine_bps as i128 / 10000;
+    let interest = overdue_amount * config.monthly_interest_bps as i128 * days_late as i128 / (30 * 10000);
+    (fine, interest)
+}
+
+/// Divide `available` entre principal da parcela, multa e juros de mora. Pagamentos
+/// parciais de uma parcela vencida abatem o principal proporcionalmente aos encargos.
+fn split_late_payment(
+    config: &LateFeeConfig,
+    installment_remaining: i128,
+    available: i128,
+    days_late: u64,
+) -> (i128, i128, i128) {
+    if days_late == 0 {
+        return (available.min(installment_remaining), 0, 0);
+    }
+
+    let (fine, interest) = late_charges(config, installment_remaining, days_late);
+    if available >= installment_remaining + fine + interest {
+        return (installment_remaining, fine, interest);
+    }
+
+    let base = 30 * 10000;
+    let charges_rate = config.fine_bps as i128 * 30 + config.monthly_interest_bps as i128 * days_late as i128;
+    let portion = available * base / (base + charges_rate);
+    let fine = portion * config.fine_bps as i128 / 10000;
+    (portion, fine, available - portion - fine)
+}
+
+/// Encargos de atraso acumulados em todas as parcelas vencidas e não pagas
+fn late_charges_due(env: &Env, loan: &Loan) -> Result<i128, LoanError> {
+    if loan.status != LoanStatus::Active {
+        return Ok(0);
+    }
+
+    let config: LateFeeConfig = env.storage()
+        .instance()
+        .get(&"LATE_FEE_CONFIG")
+        .ok_or(LoanError::NotInitialized)?;
+    let schedule = load_schedule(env, loan.id)?;
+    let current_time = env.ledger().timestamp();
+    let periods = pause_periods(env)?;
+
+    let mut total = 0;
+    for index in loan.paid_installments..loan.installments {
+        let installment = schedule.get(index).unwrap();
+        let days_late = days_overdue(
+            installment.due_date,
+            unpaused_time(&periods, installment.due_date, current_time),
+        );
+        if days_late == 0 {
+            break;
+        }
+
+        let mut overdue_amount = installment.amount;
+        if index == loan.paid_installments {
+            overdue_amount -= loan.current_installment_paid;
+        }
+
+        let (fine, interest) = late_charges(&config, overdue_amount, days_late);
+        total += fine + interest;
+    }
+    Ok(total)
+}
+
+// ==================== COLLATERAL ====================
+
+/// A garantia precisa estar no ativo do card: sem uma fonte de preço, só assim o
+/// valor travado, o teto da execução e o abatimento da dívida estão na mesma moeda
+fn validate_collateral_terms(asset: &Address, terms: &Option<CollateralTerms>) -> Result<(), LoanError> {
+    if let Some(terms) = terms {
+        if terms.ratio_bps == 0 || terms.asset != *asset {
+            return Err(LoanError::InvalidCollateralTerms);
+        }
+    }
+    Ok(())
+}
+
+fn emit_collateral(env: &Env, topic: Symbol, collateral: &Collateral) {
+    env.events().publish((topic, collateral.owner.clone()), collateral.clone());
+}
+
+/// Trava em `key` a garantia que o card exige para `amount` de principal, se exigir.
+/// O valor é arredondado para cima para nunca ficar abaixo da razão do card.
+fn lock_collateral<K: IntoVal<Env, Val>>(
+    env: &Env,
+    key: &K,
+    owner: &Address,
+    card_id: u64,
+    amount: i128,
+) -> Result<(), LoanError> {
+    let terms: Option<CollateralTerms> = env.storage().persistent().get(&("COLLATERAL_TERMS", card_id));
+    let terms = match terms {
+        Some(terms) => terms,
+        None => return Ok(()),
+    };
+
+    let collateral = Collateral {
+        owner: owner.clone(),
+        asset: terms.asset,
+        amount: (amount * terms.ratio_bps as i128 + 9999) / 10000,
+    };
+    token::Client::new(env, &collateral.asset).transfer(
+        owner,
+        &env.current_contract_address(),
+        &collateral.amount,
+    );
+    env.storage().persistent().set(key, &collateral);
+    bump_persistent(env, key);
+
+    emit_collateral(env, COLLATERAL_LOCKED, &collateral);
+    Ok(())
+}
+
+/// Devolve ao tomador a garantia guardada em `key`, se houver
+fn release_collateral<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    let collateral: Option<Collateral> = env.storage().persistent().get(key);
+    if let Some(collateral) = collateral {
+        token::Client::new(env, &collateral.asset).transfer(
+            &env.current_contract_address(),
+            &collateral.owner,
+            &collateral.amount,
+        );
+        env.storage().persistent().remove(key);
+        emit_collateral(env, COLLATERAL_RELEASED, &collateral);
+    }
+}
+
+/// Vincula ao empréstimo recém-criado a garantia travada na aplicação ou no card
+fn attach_collateral<K: IntoVal<Env, Val>>(env: &Env, key: &K, loan_id: u64) {
+    let collateral: Option<Collateral> = env.storage().persistent().get(key);
+    if let Some(collateral) = collateral {
+        env.storage().persistent().remove(key);
+        env.storage().persistent().set(&("COLLATERAL", loan_id), &collateral);
+        bump_persistent(env, &("COLLATERAL", loan_id));
+    }
+}
+
+/// Executa a garantia de um empréstimo inadimplente: os credores recebem, na
+/// proporção das cotas, até o saldo devedor e a sobra volta ao tomador. O valor
+/// repassado abate a dívida e é retornado.
+fn seize_collateral(env: &Env, loan: &mut Loan) -> Result<i128, LoanError> {
+    let key = ("COLLATERAL", loan.id);
+    let collateral: Collateral = match env.storage().persistent().get(&key) {
+        Some(collateral) => collateral,
+        None => return Ok(0),
+    };
+
+    let seized = collateral.amount.min(outstanding_balance(loan));
+    let token_client = token::Client::new(env, &collateral.asset);
+    distribute_to_lenders(env, &token_client, loan, seized)?;
+
+    let surplus = collateral.amount - seized;
+    if surplus > 0 {
+        token_client.transfer(&env.current_contract_address(), &collateral.owner, &surplus);
+    }
+    env.storage().persistent().remove(&key);
+    loan.total_paid += seized;
+
+    env.events().publish((COLLATERAL_SEIZED, loan.id), (seized, surplus));
+    Ok(seized)
+}
+
+// ==================== GUARANTORS ====================
+
+/// Transfere ao empréstimo recém-criado os avalistas da aplicação ou do card e
+/// registra o aval de cada um, pelo principal inteiro, no credit score
+fn attach_guarantors<K: IntoVal<Env, Val>>(
+    env: &Env,
+    key: &K,
+    loan_id: u64,
+    principal: i128,
+) -> Result<(), LoanError> {
+    let guarantors: Vec<Address> = match env.storage().persistent().get(key) {
+        Some(guarantors) => guarantors,
+        None => return Ok(()),
+    };
+    env.storage().persistent().remove(key);
+    env.storage().persistent().set(&("GUARANTORS", loan_id), &guarantors);
+    bump_persistent(env, &("GUARANTORS", loan_id));
+
+    let credit_score: Address = env.storage()
+        .instance()
+        .get(&"CREDIT_SCORE")
+        .ok_or(LoanError::NotInitialized)?;
+    let score_client = CreditScoreClient::new(env, &credit_score);
+    for guarantor in guarantors.iter() {
+        score_client.record_guarantee(&guarantor, &loan_id, &principal);
+    }
+    Ok(())
+}
+
+/// Atualiza no credit score a exposição de cada avalista do empréstimo; zero encerra o aval
+fn update_guarantees(env: &Env, loan_id: u64, amount: i128) -> Result<(), LoanError> {
+    let guarantors: Vec<Address> = match env.storage().persistent().get(&("GUARANTORS", loan_id)) {
+        Some(guarantors) => guarantors,
+        None => return Ok(()),
+    };
+
+    let credit_score: Address = env.storage()
+        .instance()
+        .get(&"CREDIT_SCORE")
+        .ok_or(LoanError::NotInitialized)?;
+    let score_client = CreditScoreClient::new(env, &credit_score);
+    for guarantor in guarantors.iter() {
+        score_client.record_guarantee(&guarantor, &loan_id, &amount);
+    }
+    Ok(())
+}
+
+/// Encerra os avais de um empréstimo quitado ou cancelado
+fn release_guarantors(env: &Env, loan_id: u64) -> Result<(), LoanError> {
+    update_guarantees(env, loan_id, 0)
+}
+
+/// Claim do empréstimo no fundo de proteção, se houver
+fn protection_claim(env: &Env, loan_id: u64) -> Result<Option<ProtectionClaim>, LoanError> {
+    let governance: Address = env.storage()
+        .instance()
+        .get(&"GOVERNANCE")
+        .ok_or(LoanError::NotInitialized)?;
+    Ok(GovernanceClient::new(env, &governance).get_claim(&loan_id))
+}
+
+/// Quanto ainda pode ser cobrado dos avalistas de um empréstimo inadimplente: o saldo
+/// devedor menos a cobertura que o fundo de proteção já pagou aos credores, para que
+/// aval e claim juntos nunca ultrapassem a dívida
+fn guarantee_limit(env: &Env, loan: &Loan) -> Result<i128, LoanError> {
+    let paid_by_fund = match protection_claim(env, loan.id)? {
+        Some(claim) if claim.status == ClaimStatus::Paid => claim.payout,
+        _ => 0,
+    };
+    Ok((outstanding_balance(loan) - paid_by_fund).max(0))
+}
+
+/// Parcelas vencidas até agora que ainda não foram pagas, limitadas ao saldo devedor
+fn missed_installments(env: &Env, loan: &Loan) -> Result<i128, LoanError> {
+    let schedule = load_schedule(env, loan.id)?;
+    let current_time = env.ledger().timestamp();
+
+    let mut due = 0;
+    for installment in schedule.iter() {
+        if installment.due_date > current_time {
+            break;
+        }
+        due += installment.amount;
+    }
+    Ok((due - loan.total_paid).clamp(0, outstanding_balance(loan)))
+}
+
+// ==================== AUTO DEBIT ====================
+
+/// Valor que o débito automático cobra agora: as parcelas vencidas até a data
+/// (incluindo a que vence hoje), descontado o pago da parcela atual, mais os encargos de atraso
+fn auto_debit_due(env: &Env, loan: &Loan) -> Result<i128, LoanError> {
+    let schedule = load_schedule(env, loan.id)?;
+    let current_time = env.ledger().timestamp();
+
+    let mut due = -loan.current_installment_paid;
+    for index in loan.paid_installments..loan.installments {
+        let installment = schedule.get(index).unwrap();
+        if installment.due_date > current_time {
+            break;
+        }
+        due += installment.amount;
+    }
+    Ok(due.clamp(0, outstanding_balance(loan)) + late_charges_due(env, loan)?)
+}
+
+// ==================== PIX ====================
+
+/// `Pending` pode seguir para qualquer estado e `Processing` apenas para o resultado
+/// final; `Completed`, `Expired`, `Failed` e `Cancelled` são finais.
+fn pix_transition_allowed(from: &PixStatus, to: &PixStatus) -> bool {
+    matches!(
+        (from, to),
+        (PixStatus::Pending, PixStatus::Processing)
+            | (PixStatus::Pending, PixStatus::Completed)
+            | (PixStatus::Pending, PixStatus::Expired)
+            | (PixStatus::Pending, PixStatus::Failed)
+            | (PixStatus::Pending, PixStatus::Cancelled)
+            | (PixStatus::Processing, PixStatus::Completed)
+            | (PixStatus::Processing, PixStatus::Failed)
+    )
+}
+
+fn add_pix_status_change(env: &Env, payment: &PixPayment) {
+    let key = ("PIX_HISTORY", payment.payment_id.clone());
+    let mut history: Vec<PixStatusChange> = env.storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    history.push_back(PixStatusChange {
+        status: payment.status.clone(),
+        timestamp: payment.updated_at,
+    });
+    env.storage().persistent().set(&key, &history);
+    bump_persistent(env, &key);
+}
+
+/// PIX gravados antes do layout 3 não têm `asset`; todos eram no ativo padrão.
+/// Como não há como enumerá-los na migração, o campo é preenchido na leitura.
+fn load_pix_payment(env: &Env, payment_id: String) -> Option<PixPayment> {
+    let mut fields: Map<Symbol, Val> = read_persistent(env, &("PIX_PAYMENT", payment_id))?;
+    if !fields.contains_key(symbol_short!("asset")) {
+        fields.set(symbol_short!("asset"), default_asset(env).ok()?.into_val(env));
+    }
+    let raw: Val = fields.into_val(env);
+    PixPayment::try_from_val(env, &raw).ok()
+}
+
+// ==================== MIGRATIONS ====================
+
+// Layouts de armazenamento:
+// 1 - original: parcela fixa sem cronograma, credor único, pagamentos sem encargos de atraso
+// 2 - cronograma por parcela, cotas de múltiplos credores, encargos de atraso e índices
+// 3 - ativo (token) próprio em cards, empréstimos e PIX
+
+#[derive(Clone)]
+#[contracttype]
+pub struct LoanV1 {
+    pub id: u64,
+    pub borrower: Address,
+    pub lender: Address,
+    pub amount: i128,
+    pub interest_rate: u32,
+    pub installments: u32,
+    pub installment_amount: i128,
+    pub paid_installments: u32,
+    pub total_paid: i128,
+    pub status: LoanStatus,
+    pub created_at: u64,
+    pub next_payment_date: u64,
+    pub payment_dates: Vec<u64>,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct PaymentV1 {
+    pub loan_id: u64,
+    pub installment_number: u32,
+    pub amount: i128,
+    pub paid_at: u64,
+    pub was_on_time: bool,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct InvestmentCardV1 {
+    pub id: u64,
+    pub investor: Address,
+    pub max_amount: i128,
+    pub min_amount: i128,
+    pub interest_rate: u32,
+    pub max_installments: u32,
+    pub target_risk_level: u32,
+    pub is_active: bool,
+    pub total_invested: i128,
+    pub created_at: u64,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RequestCardV1 {
+    pub id: u64,
+    pub borrower: Address,
+    pub requested_amount: i128,
+    pub desired_installments: u32,
+    pub preferred_payment_dates: Vec<u64>,
+    pub description: String,
+    pub is_active: bool,
+    pub is_funded: bool,
+    pub created_at: u64,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct LoanApplicationV1 {
+    pub id: u64,
+    pub card_id: u64,
+    pub card_type: CardType,
+    pub applicant: Address,
+    pub amount: i128,
+    pub status: ApplicationStatus,
+    pub created_at: u64,
+}
+
+/// Contratos implantados antes do versionamento não gravam a versão e estão no layout 1
+fn stored_schema_version(env: &Env) -> u32 {
+    env.storage().instance().get(&"SCHEMA_VERSION").unwrap_or(1)
+}
+
+/// Verdadeiro se o registro existe e não tem o campo `field` (introduzido após o layout 1)
+fn lacks_field<K: IntoVal<Env, Val>>(env: &Env, key: &K, field: Symbol) -> bool {
+    let raw: Option<Map<Symbol, Val>> = env.storage().persistent().get(key);
+    match raw {
+        Some(fields) => !fields.contains_key(field),
+        None => false,
+    }
+}
+
+/// Converte um empréstimo do layout 1: o cronograma é gerado a partir da parcela fixa
+/// original, o credor passa a deter 100% das cotas e o empréstimo entra nos índices.
+/// Registros já no layout atual (criados após o upgrade) são mantidos.
+fn migrate_loan_v1(env: &Env, asset: &Address, loan_id: u64) -> Result<(), LoanError> {
+    if !lacks_field(env, &("LOAN", loan_id), symbol_short!("total_due")) {
+        return Ok(());
+    }
+
+    let old: LoanV1 = env.storage()
+        .persistent()
+        .get(&("LOAN", loan_id))
+        .ok_or(LoanError::LoanNotFound)?;
+    let principal = old.amount / old.installments as i128;
+    let mut schedule: Vec<Installment> = Vec::new(env);
+    for number in 1..=old.installments {
+        let due_date = old.payment_dates
+            .get(number - 1)
+            .unwrap_or(old.created_at + number as u64 * 30 * DAY_IN_SECONDS);
+        schedule.push_back(Installment {
+            number,
+            due_date,
+            principal,
+            interest: old.installment_amount - principal,
+            amount: old.installment_amount,
+        });
+    }
+
+    let loan = Loan {
+        id: old.id,
+        borrower: old.borrower,
+        lender: old.lender.clone(),
+        asset: asset.clone(),
+        amount: old.amount,
+        interest_rate: old.interest_rate,
+        amortization: AmortizationMethod::Price,  // Parcelas fixas, como no layout 1
+        installments: old.installments,
+        installment_amount: old.installment_amount,
+        total_due: old.installment_amount * old.installments as i128,
+        paid_installments: old.paid_installments,
+        current_installment_paid: 0,
+        total_paid: old.total_paid,
+        late_charges_paid: 0,
+        status: old.status,
+        created_at: old.created_at,
+        next_payment_date: old.next_payment_date,
+        payment_dates: old.payment_dates,
+    };
+    let mut shares: Vec<LoanShare> = Vec::new(env);
+    shares.push_back(LoanShare {
+        lender: old.lender,
+        amount: old.amount,
+    });
+
+    let old_payments: Option<Vec<PaymentV1>> = env.storage().persistent().get(&("PAYMENTS", loan_id));
+    if let Some(old_payments) = old_payments {
+        let mut payments: Vec<Payment> = Vec::new(env);
+        for payment in old_payments.iter() {
+            payments.push_back(Payment {
+                loan_id: payment.loan_id,
+                installment_number: payment.installment_number,
+                amount: payment.amount,
+                late_fee: 0,
+                late_interest: 0,
+                paid_at: payment.paid_at,
+                was_on_time: payment.was_on_time,
+            });
+        }
+        env.storage().persistent().set(&("PAYMENTS", loan_id), &payments);
+    }
+
+    env.storage().persistent().set(&("LOAN", loan_id), &loan);
+    env.storage().persistent().set(&("SHARES", loan_id), &shares);
+    env.storage().persistent().set(&("SCHEDULE", loan_id), &schedule);
+    index_new_loan(env, &loan, &shares);
+    bump_loan(env, loan_id);
+    Ok(())
+}
+
+/// Converte um card de investimento do layout 1. Esses cards não custodiavam capital,
+/// então entram com `escrowed_amount = 0`: continuam listados, mas só aceitam novas
+/// aplicações se o investidor recriar o card com custódia.
+fn migrate_investment_card_v1(env: &Env, asset: &Address, card_id: u64) -> Result<(), LoanError> {
+    let key = ("INV_CARD", card_id);
+    if !lacks_field(env, &key, Symbol::new(env, "escrowed_amount")) {
+        return Ok(());
+    }
+
+    let old: InvestmentCardV1 = env.storage().persistent().get(&key).ok_or(LoanError::CardNotFound)?;
+    let card = InvestmentCard {
+        id: old.id,
+        investor: old.investor,
+        asset: asset.clone(),
+        max_amount: old.max_amount,
+        min_amount: old.min_amount,
+        interest_rate: old.interest_rate,
+        max_installments: old.max_installments,
+        target_risk_level: old.target_risk_level,
+        amortization: AmortizationMethod::Price,
+        is_active: old.is_active,
+        total_invested: old.total_invested,
+        escrowed_amount: 0,
+        created_at: old.created_at,
+    };
+    env.storage().persistent().set(&key, &card);
+    if card.is_active {
+        index_add(env, &"ACTIVE_INV_CARDS", card_id);
+    }
+    bump_card(env, card_id);
+    Ok(())
+}
+
+/// Converte um card de solicitação do layout 1. No layout 1 a taxa era escolhida por
+/// quem financiava e não havia captação coletiva: o card entra sem taxa e com o prazo
+/// de captação encerrado na criação, de modo que não pode ser financiado a 0% e o
+/// tomador o encerra (`cancel_card`) para publicar um novo pedido.
+fn migrate_request_card_v1(env: &Env, asset: &Address, card_id: u64) -> Result<(), LoanError> {
+    let key = ("REQ_CARD", card_id);
+    if !lacks_field(env, &key, Symbol::new(env, "funding_deadline")) {
+        return Ok(());
+    }
+
+    let old: RequestCardV1 = env.storage().persistent().get(&key).ok_or(LoanError::CardNotFound)?;
+    let card = RequestCard {
+        id: old.id,
+        borrower: old.borrower,
+        asset: asset.clone(),
+        requested_amount: old.requested_amount,
+        desired_installments: old.desired_installments,
+        preferred_payment_dates: old.preferred_payment_dates,
+        amortization: AmortizationMethod::Price,
+        interest_rate: 0,
+        funded_amount: if old.is_funded { old.requested_amount } else { 0 },
+        funding_deadline: old.created_at,
+        description: old.description,
+        is_active: old.is_active,
+        is_funded: old.is_funded,
+        created_at: old.created_at,
+    };
+    env.storage().persistent().set(&key, &card);
+    if card.is_active && !card.is_funded {
+        index_add(env, &"OPEN_REQ_CARDS", card_id);
+    }
+    bump_card(env, card_id);
+    Ok(())
+}
+
+/// Converte uma aplicação do layout 1: ganha o prazo de decisão padrão contado da
+/// criação e entra no índice de aplicações do card
+fn migrate_application_v1(env: &Env, app_id: u64) -> Result<(), LoanError> {
+    let key = ("APP", app_id);
+    if !lacks_field(env, &key, Symbol::new(env, "expires_at")) {
+        return Ok(());
+    }
+
+    let old: LoanApplicationV1 = env.storage().persistent().get(&key).ok_or(LoanError::ApplicationNotFound)?;
+    let ttl: u64 = env.storage().instance().get(&"APP_TTL").unwrap_or(DEFAULT_APPLICATION_TTL);
+    let application = LoanApplication {
+        id: old.id,
+        card_id: old.card_id,
+        card_type: old.card_type,
+        applicant: old.applicant,
+        amount: old.amount,
+        status: old.status,
+        reason_code: None,
+        created_at: old.created_at,
+        expires_at: old.created_at + ttl,
+    };
+
+    index_add(env, &("CARD_APPS", application.card_id), app_id);
+    env.storage().persistent().set(&key, &application);
+    bump_persistent(env, &key);
+    bump_card(env, application.card_id);
+    Ok(())
+}
+
+/// Registros do layout 2 não têm `asset`: todos usavam o ativo padrão
+fn backfill_asset<K: IntoVal<Env, Val>>(env: &Env, key: &K, asset: &Address) {
+    let raw: Option<Map<Symbol, Val>> = env.storage().persistent().get(key);
+    if let Some(mut fields) = raw {
+        if !fields.contains_key(symbol_short!("asset")) {
+            fields.set(symbol_short!("asset"), asset.into_val(env));
+            env.storage().persistent().set(key, &fields);
+            bump_persistent(env, key);
+        }
+    }
+}
+
+// ==================== CONTRACT ====================
+
+#[contract]
+pub struct LoanContract;
+
+#[contractimpl]
+impl LoanContract {
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        token: Address,
+        governance_contract: Address,
+        credit_score_contract: Address,
+    ) -> Result<(), LoanError> {
+        if env.storage().instance().has(&"ADMIN") {
+            return Err(LoanError::AlreadyInitialized);
+        }
+        bump_instance(&env);
+        admin.require_auth();
+        
+        env.storage().instance().set(&"ADMIN", &admin);
+        env.storage().instance().set(&"TOKEN", &token);
+        env.storage().instance().set(&"ASSETS", &vec![&env, token]);
+        env.storage().instance().set(&"GOVERNANCE", &governance_contract);
+        env.storage().instance().set(&"CREDIT_SCORE", &credit_score_contract);
+        env.storage().instance().set(&"NEXT_LOAN_ID", &1u64);
+        env.storage().instance().set(&"NEXT_CARD_ID", &1u64);
+        env.storage().instance().set(&"NEXT_APP_ID", &1u64);
+        env.storage().instance().set(&"LATE_FEE_CONFIG", &LateFeeConfig {
+            fine_bps: MAX_LATE_FINE_BPS,
+            monthly_interest_bps: MAX_DEFAULT_INTEREST_BPS,
+        });
+        env.storage().instance().set(&"APP_TTL", &DEFAULT_APPLICATION_TTL);
+        env.storage().instance().set(&"GRACE_PERIOD", &DEFAULT_GRACE_PERIOD);
+        env.storage().instance().set(&"SCHEMA_VERSION", &SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// Troca o WASM mantendo empréstimos, cards e pagamentos PIX. Registros em layouts
+    /// anteriores continuam ilegíveis até `migrate` percorrer todos os IDs; pausar antes.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        env.events().publish((CONTRACT_UPGRADED,), new_wasm_hash);
+        Ok(())
+    }
+
+    /// Converte até `batch_size` IDs de empréstimos, cards e aplicações de layouts anteriores,
+    /// continuando de onde o lote anterior parou, e retorna quantos ainda faltam. Ao
+    /// chegar a zero grava a versão atual do schema. Recomenda-se pausar o contrato
+    /// durante a migração.
+    pub fn migrate(env: Env, batch_size: u32) -> Result<u64, LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        if stored_schema_version(&env) >= SCHEMA_VERSION {
+            return Ok(0);
+        }
+
+        let next_loan_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_LOAN_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        let next_card_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_CARD_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        let next_app_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_APP_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        let next_id = next_loan_id.max(next_card_id).max(next_app_id);
+        let asset = default_asset(&env)?;
+
+        // Um único cursor percorre os IDs de empréstimos, cards e aplicações
+        let mut cursor: u64 = env.storage().instance().get(&"MIGRATION_CURSOR").unwrap_or(1);
+        let end = next_id.min(cursor + batch_size as u64);
+        while cursor < end {
+            migrate_loan_v1(&env, &asset, cursor)?;
+            migrate_investment_card_v1(&env, &asset, cursor)?;
+            migrate_request_card_v1(&env, &asset, cursor)?;
+            migrate_application_v1(&env, cursor)?;
+            backfill_asset(&env, &("LOAN", cursor), &asset);
+            backfill_asset(&env, &("INV_CARD", cursor), &asset);
+            backfill_asset(&env, &("REQ_CARD", cursor), &asset);
+            cursor += 1;
+        }
+
+        if cursor < next_id {
+            env.storage().instance().set(&"MIGRATION_CURSOR", &cursor);
+            return Ok(next_id - cursor);
+        }
+
+        // Configurações que não existiam no layout 1
+        if !env.storage().instance().has(&"LATE_FEE_CONFIG") {
+            env.storage().instance().set(&"LATE_FEE_CONFIG", &LateFeeConfig {
+                fine_bps: MAX_LATE_FINE_BPS,
+                monthly_interest_bps: MAX_DEFAULT_INTEREST_BPS,
+            });
+        }
+        if !env.storage().instance().has(&"ASSETS") {
+            env.storage().instance().set(&"ASSETS", &vec![&env, asset]);
+        }
+        env.storage().instance().remove(&"MIGRATION_CURSOR");
+        env.storage().instance().set(&"SCHEMA_VERSION", &SCHEMA_VERSION);
+        env.events().publish((SCHEMA_MIGRATED,), SCHEMA_VERSION);
+        Ok(0)
+    }
+
+    pub fn get_schema_version(env: Env) -> u32 {
+        bump_instance(&env);
+        stored_schema_version(&env)
+    }
+
+    /// Prazo (em segundos) para o investidor decidir sobre uma aplicação
+    pub fn set_application_ttl(env: Env, ttl: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        if ttl == 0 {
+            return Err(LoanError::InvalidApplicationTtl);
+        }
+        env.storage().instance().set(&"APP_TTL", &ttl);
+        Ok(())
+    }
+
+    pub fn get_application_ttl(env: Env) -> u64 {
+        bump_instance(&env);
+        env.storage().instance().get(&"APP_TTL").unwrap_or(DEFAULT_APPLICATION_TTL)
+    }
+
+    /// Define a carência, em segundos, entre o vencimento de uma parcela e a inadimplência
+    pub fn set_grace_period(env: Env, grace_period: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        if grace_period == 0 {
+            return Err(LoanError::InvalidGracePeriod);
+        }
+        env.storage().instance().set(&"GRACE_PERIOD", &grace_period);
+        Ok(())
+    }
+
+    pub fn get_grace_period(env: Env) -> u64 {
+        bump_instance(&env);
+        env.storage().instance().get(&"GRACE_PERIOD").unwrap_or(DEFAULT_GRACE_PERIOD)
+    }
+
+    pub fn set_late_fee_config(env: Env, fine_bps: u32, monthly_interest_bps: u32) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        if fine_bps > MAX_LATE_FINE_BPS || monthly_interest_bps > MAX_DEFAULT_INTEREST_BPS {
+            return Err(LoanError::LateFeeAboveCap);
+        }
+
+        env.storage().instance().set(&"LATE_FEE_CONFIG", &LateFeeConfig {
+            fine_bps,
+            monthly_interest_bps,
+        });
+        Ok(())
+    }
+
+    pub fn get_late_fee_config(env: Env) -> Result<LateFeeConfig, LoanError> {
+        bump_instance(&env);
+        env.storage().instance().get(&"LATE_FEE_CONFIG").ok_or(LoanError::NotInitialized)
+    }
+
+    /// Aceita um novo ativo (token SAC ou contrato de token) para cards e empréstimos
+    pub fn add_asset(env: Env, asset: Address) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut assets = allowed_assets(&env)?;
+        if !assets.contains(&asset) {
+            assets.push_back(asset.clone());
+            env.storage().instance().set(&"ASSETS", &assets);
+            env.events().publish((ASSET_ADDED,), asset);
+        }
+        Ok(())
+    }
+
+    /// Deixa de aceitar o ativo em novos cards; cards e empréstimos existentes seguem nele
+    pub fn remove_asset(env: Env, asset: Address) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut assets = allowed_assets(&env)?;
+        let index = assets.first_index_of(&asset).ok_or(LoanError::AssetNotAllowed)?;
+        assets.remove(index);
+        env.storage().instance().set(&"ASSETS", &assets);
+        env.events().publish((ASSET_REMOVED,), asset);
+        Ok(())
+    }
+
+    pub fn get_assets(env: Env) -> Result<Vec<Address>, LoanError> {
+        bump_instance(&env);
+        allowed_assets(&env)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_investment_card(
+        env: Env,
+        investor: Address,
+        asset: Address,
+        max_amount: i128,
+        min_amount: i128,
+        interest_rate: u32,
+        max_installments: u32,
+        target_risk_level: u32,
+        amortization: AmortizationMethod,
+        collateral: Option<CollateralTerms>,
+    ) -> Result<u64, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, true)?;
+        investor.require_auth();
+
+        if min_amount <= 0 || min_amount > max_amount {
+            return Err(LoanError::InvalidAmount);
+        }
+        if max_installments == 0 || max_installments > MAX_INSTALLMENTS {
+            return Err(LoanError::InstallmentsOutOfRange);
+        }
+        ensure_asset_allowed(&env, &asset)?;
+        validate_collateral_terms(&asset, &collateral)?;
+
+        // O capital do card fica em custódia no contrato até ser emprestado ou devolvido
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&investor, &env.current_contract_address(), &max_amount);
+
+        let card_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_CARD_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        
+        let card = InvestmentCard {
+            id: card_id,
+            investor: investor.clone(),
+            asset,
+            max_amount,
+            min_amount,
+            interest_rate,
+            max_installments,
+            target_risk_level,
+            amortization,
+            is_active: true,
+            total_invested: 0,
+            escrowed_amount: max_amount,
+            created_at: env.ledger().timestamp(),
+        };
+
+        env.storage().persistent().set(&("INV_CARD", card_id), &card);
+        if let Some(terms) = collateral {
+            env.storage().persistent().set(&("COLLATERAL_TERMS", card_id), &terms);
+        }
+        env.storage().instance().set(&"NEXT_CARD_ID", &(card_id + 1));
+        index_add(&env, &"ACTIVE_INV_CARDS", card_id);
+        bump_card(&env, card_id);
+
+        emit_card_created(&env, card_id, investor, true);
+        Ok(card_id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_request_card(
+        env: Env,
+        borrower: Address,
+        asset: Address,
+        requested_amount: i128,
+        desired_installments: u32,
+        preferred_payment_dates: Vec<u64>,
+        amortization: AmortizationMethod,
+        interest_rate: u32,
+        funding_deadline: u64,
+        description: String,
+        collateral: Option<CollateralTerms>,
+    ) -> Result<u64, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, true)?;
+        borrower.require_auth();
+
+        if requested_amount <= 0 {
+            return Err(LoanError::InvalidAmount);
+        }
+        if desired_installments == 0 || desired_installments > MAX_INSTALLMENTS {
+            return Err(LoanError::InstallmentsOutOfRange);
+        }
+        if funding_deadline <= env.ledger().timestamp() {
+            return Err(LoanError::InvalidFundingDeadline);
+        }
+        ensure_asset_allowed(&env, &asset)?;
+        validate_collateral_terms(&asset, &collateral)?;
+
+        let card_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_CARD_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        
+        let card = RequestCard {
             id: card_id,
             borrower: borrower.clone(),
+            asset,
             requested_amount,
             desired_installments,
             preferred_payment_dates,
+            amortization,
+            interest_rate,
+            funded_amount: 0,
+            funding_deadline,
             description,
             is_active: true,
             is_funded: false,
@@ -303,262 +2172,1653 @@ impl LoanContract {
         };
 
         env.storage().persistent().set(&("REQ_CARD", card_id), &card);
-        env.storage().instance().set(&"NEXT_CARD_ID", &(card_id + 1));
+        env.storage().instance().set(&"NEXT_CARD_ID", &(card_id + 1));
+        if let Some(terms) = collateral {
+            env.storage().persistent().set(&("COLLATERAL_TERMS", card_id), &terms);
+            // O tomador trava a garantia ao publicar o pedido; ela segue para o empréstimo
+            // quando o card for financiado ou volta se o card não for financiado
+            lock_collateral(&env, &("REQ_COLLATERAL", card_id), &borrower, card_id, requested_amount)?;
+        }
+        index_add(&env, &"OPEN_REQ_CARDS", card_id);
+        bump_card(&env, card_id);
+
+        emit_card_created(&env, card_id, borrower, false);
+        Ok(card_id)
+    }
+
+    pub fn apply_to_investment_card(
+        env: Env,
+        borrower: Address,
+        card_id: u64,
+        amount: i128,
+    ) -> Result<u64, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, true)?;
+        borrower.require_auth();
+
+        let card: InvestmentCard = env.storage()
+            .persistent()
+            .get(&("INV_CARD", card_id))
+            .ok_or(LoanError::CardNotFound)?;
+        
+        if !card.is_active {
+            return Err(LoanError::CardNotActive);
+        }
+        if amount < card.min_amount || amount > card.max_amount {
+            return Err(LoanError::AmountOutOfRange);
+        }
+        if amount + origination_fee(&fee_config(&env)?, amount) > card.escrowed_amount {
+            return Err(LoanError::InsufficientEscrow);
+        }
+
+        check_borrower_eligibility(&env, &borrower, amount, card.target_risk_level)?;
+
+        let app_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_APP_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        let created_at = env.ledger().timestamp();
+
+        // Se o card exige garantia, ela é travada já na aplicação
+        lock_collateral(&env, &("APP_COLLATERAL", app_id), &borrower, card_id, amount)?;
+        
+        let application = LoanApplication {
+            id: app_id,
+            card_id,
+            card_type: CardType::Investment,
+            applicant: borrower,
+            amount,
+            status: ApplicationStatus::Pending,
+            reason_code: None,
+            created_at,
+            expires_at: created_at + Self::get_application_ttl(env.clone()),
+        };
+
+        let mut card_apps: Vec<u64> = env.storage()
+            .persistent()
+            .get(&("CARD_APPS", card_id))
+            .unwrap_or(Vec::new(&env));
+        card_apps.push_back(app_id);
+
+        env.storage().persistent().set(&("APP", app_id), &application);
+        env.storage().persistent().set(&("CARD_APPS", card_id), &card_apps);
+        env.storage().instance().set(&"NEXT_APP_ID", &(app_id + 1));
+        bump_persistent(&env, &("APP", app_id));
+        bump_card(&env, card_id);
+
+        emit_application(&env, APPLICATION_SUBMITTED, &application);
+        Ok(app_id)
+    }
+
+    pub fn approve_application(
+        env: Env,
+        app_id: u64,
+        installments: u32,
+        payment_dates: Vec<u64>,
+    ) -> Result<u64, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, true)?;
+        let mut application: LoanApplication = env.storage()
+            .persistent()
+            .get(&("APP", app_id))
+            .ok_or(LoanError::ApplicationNotFound)?;
+
+        if application.status != ApplicationStatus::Pending {
+            return Err(LoanError::ApplicationNotPending);
+        }
+        if env.ledger().timestamp() > application.expires_at {
+            return Err(LoanError::ApplicationExpired);
+        }
+
+        let mut card: InvestmentCard = env.storage()
+            .persistent()
+            .get(&("INV_CARD", application.card_id))
+            .ok_or(LoanError::CardNotFound)?;
+
+        card.investor.require_auth();
+
+        if !card.is_active {
+            return Err(LoanError::CardNotActive);
+        }
+        if installments == 0 || installments > card.max_installments {
+            return Err(LoanError::InstallmentsOutOfRange);
+        }
+
+        let fee = origination_fee(&fee_config(&env)?, application.amount);
+        if application.amount + fee > card.escrowed_amount {
+            return Err(LoanError::InsufficientEscrow);
+        }
+
+        let mut shares = Vec::new(&env);
+        shares.push_back(LoanShare {
+            lender: card.investor.clone(),
+            amount: application.amount,
+        });
+
+        // O empréstimo é financiado com o capital em custódia no próprio contrato
+        let loan_id = Self::create_loan_internal(
+            &env,
+            application.applicant.clone(),
+            card.asset.clone(),
+            shares,
+            application.amount,
+            card.interest_rate,
+            card.amortization.clone(),
+            installments,
+            payment_dates,
+        )?;
+        // A taxa sai da custódia do card e fica reservada até o desembolso
+        env.storage().persistent().set(&("LOAN_FEES", loan_id), &vec![&env, FundingCommitment {
+            lender: card.investor.clone(),
+            amount: application.amount,
+            fee,
+        }]);
+        env.storage()
+            .persistent()
+            .set(&("LOAN_CARD", loan_id), &(CardType::Investment, application.card_id));
+        attach_collateral(&env, &("APP_COLLATERAL", app_id), loan_id);
+        attach_guarantors(&env, &("APP_GUARANTORS", app_id), loan_id, application.amount)?;
+
+        card.escrowed_amount -= application.amount + fee;
+        card.total_invested += application.amount;
+        env.storage().persistent().set(&("INV_CARD", application.card_id), &card);
+        bump_card(&env, application.card_id);
+
+        application.status = ApplicationStatus::Approved;
+        env.storage().persistent().set(&("APP", app_id), &application);
+        bump_persistent(&env, &("APP", app_id));
+        bump_loan(&env, loan_id);
+
+        emit_application(&env, APPLICATION_APPROVED, &application);
+        Ok(loan_id)
+    }
+
+    pub fn reject_application(env: Env, app_id: u64, reason_code: Option<u32>) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut application: LoanApplication = env.storage()
+            .persistent()
+            .get(&("APP", app_id))
+            .ok_or(LoanError::ApplicationNotFound)?;
+
+        if application.status != ApplicationStatus::Pending {
+            return Err(LoanError::ApplicationNotPending);
+        }
+
+        let card: InvestmentCard = env.storage()
+            .persistent()
+            .get(&("INV_CARD", application.card_id))
+            .ok_or(LoanError::CardNotFound)?;
+
+        card.investor.require_auth();
+
+        application.status = ApplicationStatus::Rejected;
+        application.reason_code = reason_code;
+        env.storage().persistent().set(&("APP", app_id), &application);
+        bump_persistent(&env, &("APP", app_id));
+        release_collateral(&env, &("APP_COLLATERAL", app_id));
+
+        emit_application(&env, APPLICATION_REJECTED, &application);
+        Ok(())
+    }
+
+    pub fn withdraw_application(env: Env, app_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut application: LoanApplication = env.storage()
+            .persistent()
+            .get(&("APP", app_id))
+            .ok_or(LoanError::ApplicationNotFound)?;
+
+        application.applicant.require_auth();
+        if application.status != ApplicationStatus::Pending {
+            return Err(LoanError::ApplicationNotPending);
+        }
+
+        application.status = ApplicationStatus::Withdrawn;
+        env.storage().persistent().set(&("APP", app_id), &application);
+        bump_persistent(&env, &("APP", app_id));
+        release_collateral(&env, &("APP_COLLATERAL", app_id));
+
+        emit_application(&env, APPLICATION_WITHDRAWN, &application);
+        Ok(())
+    }
+
+    /// Marca como expirada uma aplicação pendente cujo prazo passou (qualquer conta pode chamar)
+    pub fn expire_application(env: Env, app_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut application: LoanApplication = env.storage()
+            .persistent()
+            .get(&("APP", app_id))
+            .ok_or(LoanError::ApplicationNotFound)?;
+
+        if application.status != ApplicationStatus::Pending {
+            return Err(LoanError::ApplicationNotPending);
+        }
+        if env.ledger().timestamp() <= application.expires_at {
+            return Err(LoanError::ApplicationNotExpired);
+        }
+
+        application.status = ApplicationStatus::Expired;
+        env.storage().persistent().set(&("APP", app_id), &application);
+        bump_persistent(&env, &("APP", app_id));
+        release_collateral(&env, &("APP_COLLATERAL", app_id));
+
+        emit_application(&env, APPLICATION_EXPIRED, &application);
+        Ok(())
+    }
+
+    /// Nomeia um avalista para uma aplicação pendente (`is_application`) ou para um
+    /// card de solicitação ainda não financiado. Tomador e avalista autorizam; o
+    /// avalista precisa ter limite no credit score para o principal inteiro.
+    pub fn add_guarantor(env: Env, guarantor: Address, id: u64, is_application: bool) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, true)?;
+
+        let (borrower, amount, key) = if is_application {
+            let application: LoanApplication = env.storage()
+                .persistent()
+                .get(&("APP", id))
+                .ok_or(LoanError::ApplicationNotFound)?;
+            if application.status != ApplicationStatus::Pending {
+                return Err(LoanError::ApplicationNotPending);
+            }
+            (application.applicant, application.amount, ("APP_GUARANTORS", id))
+        } else {
+            let card: RequestCard = env.storage()
+                .persistent()
+                .get(&("REQ_CARD", id))
+                .ok_or(LoanError::CardNotFound)?;
+            if !card.is_active || card.is_funded {
+                return Err(LoanError::CardNotActive);
+            }
+            (card.borrower, card.requested_amount, ("REQ_GUARANTORS", id))
+        };
+
+        borrower.require_auth();
+        guarantor.require_auth();
+
+        let mut guarantors: Vec<Address> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(&env));
+        if guarantor == borrower || guarantors.contains(&guarantor) || guarantors.len() >= MAX_GUARANTORS {
+            return Err(LoanError::InvalidGuarantor);
+        }
+        check_borrower_eligibility(&env, &guarantor, amount, 0)?;
+
+        guarantors.push_back(guarantor.clone());
+        env.storage().persistent().set(&key, &guarantors);
+        bump_persistent(&env, &key);
+
+        env.events().publish((GUARANTOR_ADDED, id), guarantor);
+        Ok(())
+    }
+
+    /// Compromete `amount` de um credor com o card de solicitação. O valor (mais a
+    /// taxa de originação proporcional) fica em custódia até a captação completar;
+    /// quando isso acontece o empréstimo é criado e o seu ID é retornado.
+    pub fn fund_request_card(
+        env: Env,
+        lender: Address,
+        card_id: u64,
+        amount: i128,
+    ) -> Result<Option<u64>, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, true)?;
+        lender.require_auth();
+
+        let mut card: RequestCard = env.storage()
+            .persistent()
+            .get(&("REQ_CARD", card_id))
+            .ok_or(LoanError::CardNotFound)?;
+
+        if !card.is_active || card.is_funded {
+            return Err(LoanError::CardNotActive);
+        }
+        if env.ledger().timestamp() > card.funding_deadline {
+            return Err(LoanError::FundingDeadlinePassed);
+        }
+        if amount <= 0 || card.funded_amount + amount > card.requested_amount {
+            return Err(LoanError::AmountOutOfRange);
+        }
+
+        check_borrower_eligibility(&env, &card.borrower, card.requested_amount, 0)?;
+
+        let mut commitments: Vec<FundingCommitment> = env.storage()
+            .persistent()
+            .get(&("REQ_FUNDS", card_id))
+            .unwrap_or(Vec::new(&env));
+
+        // Aportes repetidos do mesmo credor são somados numa única posição, e a taxa
+        // reservada é recalculada sobre o total para não acumular arredondamentos
+        let mut position = None;
+        for index in 0..commitments.len() {
+            if commitments.get(index).unwrap().lender == lender {
+                position = Some(index);
+                break;
+            }
+        }
+        let mut commitment = match position {
+            Some(index) => commitments.get(index).unwrap(),
+            None => FundingCommitment {
+                lender: lender.clone(),
+                amount: 0,
+                fee: 0,
+            },
+        };
+        let config = fee_config(&env)?;
+        let reserved_fee = origination_fee(&config, commitment.amount + amount).max(commitment.fee);
+        let fee = reserved_fee - commitment.fee;
+
+        let token_client = token::Client::new(&env, &card.asset);
+        token_client.transfer(&lender, &env.current_contract_address(), &(amount + fee));
+
+        commitment.amount += amount;
+        commitment.fee = reserved_fee;
+        match position {
+            Some(index) => commitments.set(index, commitment),
+            None => commitments.push_back(commitment),
+        }
+
+        card.funded_amount += amount;
+        emit_funding_committed(&env, card_id, lender, amount, card.funded_amount);
+
+        let mut loan_id = None;
+        if card.funded_amount == card.requested_amount {
+            let mut shares = Vec::new(&env);
+            for commitment in commitments.iter() {
+                // Um aumento das taxas durante a captação não pode ser coberto com a custódia de outros
+                if origination_fee(&config, commitment.amount) > commitment.fee {
+                    return Err(LoanError::FeeConfigChanged);
+                }
+                shares.push_back(LoanShare {
+                    lender: commitment.lender,
+                    amount: commitment.amount,
+                });
+            }
+
+            loan_id = Some(Self::create_loan_internal(
+                &env,
+                card.borrower.clone(),
+                card.asset.clone(),
+                shares,
+                card.requested_amount,
+                card.interest_rate,
+                card.amortization.clone(),
+                card.desired_installments,
+                card.preferred_payment_dates.clone(),
+            )?);
+            attach_collateral(&env, &("REQ_COLLATERAL", card_id), loan_id.unwrap());
+            attach_guarantors(&env, &("REQ_GUARANTORS", card_id), loan_id.unwrap(), card.requested_amount)?;
+
+            // As taxas reservadas passam ao empréstimo e só são cobradas no desembolso
+            env.storage().persistent().set(&("LOAN_FEES", loan_id.unwrap()), &commitments);
+            env.storage()
+                .persistent()
+                .set(&("LOAN_CARD", loan_id.unwrap()), &(CardType::Request, card_id));
+
+            card.is_funded = true;
+            index_remove(&env, &"OPEN_REQ_CARDS", card_id);
+            bump_loan(&env, loan_id.unwrap());
+        }
+
+        env.storage().persistent().set(&("REQ_FUNDS", card_id), &commitments);
+        env.storage().persistent().set(&("REQ_CARD", card_id), &card);
+        bump_card(&env, card_id);
 
-        emit_card_created(&env, card_id, borrower, false);
-        card_id
+        Ok(loan_id)
+    }
+
+    /// Devolve as contribuições de um card que não completou a captação no prazo.
+    /// Pode ser chamada por qualquer conta depois do `funding_deadline`.
+    pub fn refund_request_card(env: Env, card_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut card: RequestCard = env.storage()
+            .persistent()
+            .get(&("REQ_CARD", card_id))
+            .ok_or(LoanError::CardNotFound)?;
+
+        if card.is_funded {
+            return Err(LoanError::CardAlreadyFunded);
+        }
+        if env.ledger().timestamp() <= card.funding_deadline {
+            return Err(LoanError::FundingDeadlineNotReached);
+        }
+
+        Self::refund_commitments(&env, &mut card)?;
+        card.is_active = false;
+        env.storage().persistent().set(&("REQ_CARD", card_id), &card);
+        bump_card(&env, card_id);
+        index_remove(&env, &"OPEN_REQ_CARDS", card_id);
+        Ok(())
+    }
+
+    fn refund_commitments(env: &Env, card: &mut RequestCard) -> Result<(), LoanError> {
+        let commitments: Vec<FundingCommitment> = env.storage()
+            .persistent()
+            .get(&("REQ_FUNDS", card.id))
+            .unwrap_or(Vec::new(env));
+
+        let token_client = token::Client::new(env, &card.asset);
+
+        for commitment in commitments.iter() {
+            let refund = commitment.amount + commitment.fee;
+            token_client.transfer(&env.current_contract_address(), &commitment.lender, &refund);
+            emit_funding_refunded(env, card.id, commitment.lender, refund);
+        }
+
+        card.funded_amount = 0;
+        env.storage().persistent().remove(&("REQ_FUNDS", card.id));
+        release_collateral(env, &("REQ_COLLATERAL", card.id));
+        Ok(())
+    }
+
+    /// Cria o empréstimo em `Pending` com o capital em custódia no contrato até a
+    /// confirmação do PIX de desembolso (`activate_loan`). `shares` define o principal
+    /// de cada credor; o chamador reserva as taxas de originação em `LOAN_FEES`, cobradas
+    /// na ativação.
+    #[allow(clippy::too_many_arguments)]
+    fn create_loan_internal(
+        env: &Env,
+        borrower: Address,
+        asset: Address,
+        shares: Vec<LoanShare>,
+        amount: i128,
+        interest_rate: u32,
+        amortization: AmortizationMethod,
+        installments: u32,
+        payment_dates: Vec<u64>,
+    ) -> Result<u64, LoanError> {
+        let loan_id: u64 = env.storage()
+            .instance()
+            .get(&"NEXT_LOAN_ID")
+            .ok_or(LoanError::NotInitialized)?;
+        let current_time = env.ledger().timestamp();
+
+        let schedule = build_schedule(
+            env,
+            amount,
+            interest_rate,
+            &amortization,
+            installments,
+            &payment_dates,
+            current_time,
+        )?;
+
+        let mut total_due: i128 = 0;
+        for installment in schedule.iter() {
+            total_due += installment.amount;
+        }
+        let first = schedule.get(0).unwrap();
+        let lender = lead_lender(&shares);
+
+        let loan = Loan {
+            id: loan_id,
+            borrower: borrower.clone(),
+            lender: lender.clone(),
+            asset,
+            amount,
+            interest_rate,
+            amortization,
+            installments,
+            installment_amount: first.amount,
+            total_due,
+            paid_installments: 0,
+            current_installment_paid: 0,
+            total_paid: 0,
+            late_charges_paid: 0,
+            status: LoanStatus::Pending,
+            created_at: current_time,
+            next_payment_date: first.due_date,
+            payment_dates,
+        };
+
+        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        env.storage().persistent().set(&("SHARES", loan_id), &shares);
+        env.storage().persistent().set(&("SCHEDULE", loan_id), &schedule);
+        env.storage().instance().set(&"NEXT_LOAN_ID", &(loan_id + 1));
+        index_new_loan(env, &loan, &shares);
+        bump_loan(env, loan_id);
+
+        emit_loan_created(env, loan_id, borrower, lender, amount);
+        Ok(loan_id)
+    }
+
+    /// PIX de desembolso confirmado: ativa o empréstimo, cobra as taxas de originação
+    /// reservadas, refaz o cronograma a partir da data efetiva do desembolso e libera
+    /// o principal em custódia ao tomador.
+    fn activate_loan(env: &Env, loan_id: u64) -> Result<(), LoanError> {
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if loan.status != LoanStatus::Pending {
+            return Err(LoanError::LoanNotPending);
+        }
+        charge_reserved_fees(env, &loan)?;
+
+        let schedule = build_schedule(
+            env,
+            loan.amount,
+            loan.interest_rate,
+            &loan.amortization,
+            loan.installments,
+            &loan.payment_dates,
+            env.ledger().timestamp(),
+        )?;
+        let mut total_due: i128 = 0;
+        for installment in schedule.iter() {
+            total_due += installment.amount;
+        }
+        let first = schedule.get(0).unwrap();
+        loan.installment_amount = first.amount;
+        loan.next_payment_date = first.due_date;
+        loan.total_due = total_due;
+        set_loan_status(env, &mut loan, LoanStatus::Active);
+
+        token::Client::new(env, &loan.asset).transfer(
+            &env.current_contract_address(),
+            &loan.borrower,
+            &loan.amount,
+        );
+
+        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        env.storage().persistent().set(&("SCHEDULE", loan_id), &schedule);
+        bump_loan(env, loan_id);
+
+        env.events().publish((LOAN_ACTIVATED, loan_id), loan.next_payment_date);
+        Ok(())
+    }
+
+    /// PIX de desembolso falhou, expirou ou foi cancelado: cancela o empréstimo e
+    /// devolve a cada credor o principal em custódia e a taxa de originação reservada.
+    fn cancel_pending_loan(env: &Env, loan_id: u64) -> Result<(), LoanError> {
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if loan.status != LoanStatus::Pending {
+            return Err(LoanError::LoanNotPending);
+        }
+
+        let shares = load_shares(env, loan_id)?;
+        let token_client = token::Client::new(env, &loan.asset);
+        for share in shares.iter() {
+            token_client.transfer(&env.current_contract_address(), &share.lender, &share.amount);
+        }
+        refund_reserved_fees(env, &loan);
+        Self::release_origin_card(env, &loan)?;
+
+        set_loan_status(env, &mut loan, LoanStatus::Cancelled);
+        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        bump_loan(env, loan_id);
+        release_collateral(env, &("COLLATERAL", loan_id));
+        release_guarantors(env, loan_id)?;
+
+        env.events().publish((LOAN_CANCELLED, loan_id), loan.amount);
+        Ok(())
+    }
+
+    /// O principal de um empréstimo cancelado já voltou aos credores: o card de origem
+    /// deixa de contá-lo. Um card de investimento reduz `total_invested`; um card de
+    /// solicitação perde a captação e é encerrado, pois a garantia dele foi liberada
+    /// e o tomador precisa publicar um novo pedido.
+    fn release_origin_card(env: &Env, loan: &Loan) -> Result<(), LoanError> {
+        let origin: Option<(CardType, u64)> = env.storage().persistent().get(&("LOAN_CARD", loan.id));
+        match origin {
+            Some((CardType::Investment, card_id)) => {
+                let mut card: InvestmentCard = env.storage()
+                    .persistent()
+                    .get(&("INV_CARD", card_id))
+                    .ok_or(LoanError::CardNotFound)?;
+                card.total_invested -= loan.amount;
+                env.storage().persistent().set(&("INV_CARD", card_id), &card);
+                bump_card(env, card_id);
+            }
+            Some((CardType::Request, card_id)) => {
+                let mut card: RequestCard = env.storage()
+                    .persistent()
+                    .get(&("REQ_CARD", card_id))
+                    .ok_or(LoanError::CardNotFound)?;
+                card.funded_amount = 0;
+                card.is_funded = false;
+                card.is_active = false;
+                env.storage().persistent().remove(&("REQ_FUNDS", card_id));
+                env.storage().persistent().set(&("REQ_CARD", card_id), &card);
+                bump_card(env, card_id);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Cancela um empréstimo pendente cujo PIX de desembolso não foi registrado até
+    /// `PENDING_LOAN_EXPIRY` após a criação, devolvendo principal e taxas aos credores.
+    /// Qualquer conta pode chamar.
+    pub fn expire_pending_loan(env: Env, loan_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        if loan.status != LoanStatus::Pending {
+            return Err(LoanError::LoanNotPending);
+        }
+        if env.storage().persistent().has(&("LOAN_PAYOUT", loan_id)) {
+            return Err(LoanError::PayoutAlreadyRegistered);
+        }
+        if env.ledger().timestamp() <= loan.created_at + PENDING_LOAN_EXPIRY {
+            return Err(LoanError::PendingLoanNotExpired);
+        }
+
+        Self::cancel_pending_loan(&env, loan_id)
+    }
+
+    /// Paga qualquer valor do empréstimo: parcial (acumula na parcela atual),
+    /// exato ou acima da parcela (quita várias parcelas de uma vez).
+    pub fn make_payment(env: Env, loan_id: u64, amount: i128) -> Result<bool, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        loan.borrower.require_auth();
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
+        if amount <= 0 {
+            return Err(LoanError::InvalidAmount);
+        }
+        if amount > outstanding_balance(&loan) + late_charges_due(&env, &loan)? {
+            return Err(LoanError::AmountExceedsOutstanding);
+        }
+
+        token::Client::new(&env, &loan.asset).transfer(&loan.borrower, &env.current_contract_address(), &amount);
+        Self::apply_payment(&env, &mut loan, amount)?;
+
+        Ok(loan.status == LoanStatus::Completed)
     }
 
-    pub fn apply_to_investment_card(
-        env: Env,
-        borrower: Address,
-        card_id: u64,
-        amount: i128,
-    ) -> u64 {
-        borrower.require_auth();
+    /// Quita antecipadamente todo o saldo devedor e retorna o valor pago
+    pub fn pay_off_loan(env: Env, loan_id: u64) -> Result<i128, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        loan.borrower.require_auth();
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
+
+        let amount = outstanding_balance(&loan) + late_charges_due(&env, &loan)?;
+        token::Client::new(&env, &loan.asset).transfer(&loan.borrower, &env.current_contract_address(), &amount);
+        Self::apply_payment(&env, &mut loan, amount)?;
+
+        Ok(amount)
+    }
+
+    /// Distribui aos credores um valor já recebido do tomador e o aplica às parcelas
+    fn apply_payment(env: &Env, loan: &mut Loan, amount: i128) -> Result<(), LoanError> {
+        let current_time = env.ledger().timestamp();
+
+        let token_client = token::Client::new(env, &loan.asset);
+        distribute_to_lenders(env, &token_client, loan, amount)?;
+
+        let credit_score: Address = env.storage()
+            .instance()
+            .get(&"CREDIT_SCORE")
+            .ok_or(LoanError::NotInitialized)?;
+        let score_client = CreditScoreClient::new(env, &credit_score);
+        let late_fee_config: LateFeeConfig = env.storage()
+            .instance()
+            .get(&"LATE_FEE_CONFIG")
+            .ok_or(LoanError::NotInitialized)?;
+
+        let schedule = load_schedule(env, loan.id)?;
+
+        let mut payments: Vec<Payment> = env.storage()
+            .persistent()
+            .get(&("PAYMENTS", loan.id))
+            .unwrap_or(Vec::new(env));
+        let periods = pause_periods(env)?;
+
+        // Distribui o valor entre as parcelas em aberto, da mais antiga para a mais nova.
+        // Em parcelas vencidas, parte do valor cobre a multa e os juros de mora.
+        let mut remaining = amount;
+        while remaining > 0 {
+            let installment = schedule.get(loan.paid_installments).unwrap();
+            let installment_number = installment.number;
+            let installment_due = installment.amount;
+            let days_late = days_overdue(
+                installment.due_date,
+                unpaused_time(&periods, installment.due_date, current_time),
+            );
+            let on_time = days_late == 0;
+
+            let (portion, late_fee, late_interest) = split_late_payment(
+                &late_fee_config,
+                installment_due - loan.current_installment_paid,
+                remaining,
+                days_late,
+            );
+
+            remaining -= portion + late_fee + late_interest;
+            loan.current_installment_paid += portion;
+            loan.total_paid += portion;
+            loan.late_charges_paid += late_fee + late_interest;
+
+            payments.push_back(Payment {
+                loan_id: loan.id,
+                installment_number,
+                amount: portion,
+                late_fee,
+                late_interest,
+                paid_at: current_time,
+                was_on_time: on_time,
+            });
+
+            emit_payment_made(env, loan.id, loan.borrower.clone(), portion, installment_number, on_time);
+
+            if loan.current_installment_paid < installment_due {
+                break;
+            }
+
+            // Parcela quitada: alimenta o histórico on-chain do tomador no contrato de credit score
+            score_client.record_payment(&loan.borrower, &loan.id, &installment_due, &on_time);
+
+            loan.paid_installments = installment_number;
+            loan.current_installment_paid = 0;
+
+            if loan.paid_installments < loan.installments {
+                loan.next_payment_date = schedule.get(loan.paid_installments).unwrap().due_date;
+            } else {
+                set_loan_status(env, loan, LoanStatus::Completed);
+                release_collateral(env, &("COLLATERAL", loan.id));
+                release_guarantors(env, loan.id)?;
+                emit_loan_completed(env, loan.id, loan.borrower.clone(), loan.total_paid);
+            }
+        }
+
+        env.storage().persistent().set(&("PAYMENTS", loan.id), &payments);
+        env.storage().persistent().set(&("LOAN", loan.id), loan);
+        bump_loan(env, loan.id);
+        Ok(())
+    }
+
+    /// Ativa o débito automático: o tomador autoriza o contrato no token do
+    /// empréstimo (`approve`) e as parcelas passam a ser cobradas por
+    /// `collect_due_installments`. `keeper_tip` é pago a quem fizer a cobrança.
+    pub fn enable_auto_debit(env: Env, loan_id: u64, keeper_tip: i128) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        loan.borrower.require_auth();
+        if loan.status != LoanStatus::Active && loan.status != LoanStatus::Pending {
+            return Err(LoanError::LoanNotActive);
+        }
+        if keeper_tip < 0 {
+            return Err(LoanError::InvalidAmount);
+        }
+
+        env.storage().persistent().set(&("AUTO_DEBIT", loan_id), &keeper_tip);
+        bump_loan(&env, loan_id);
+
+        env.events().publish((AUTO_DEBIT_ENABLED, loan_id), keeper_tip);
+        Ok(())
+    }
+
+    pub fn disable_auto_debit(env: Env, loan_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        loan.borrower.require_auth();
+        env.storage().persistent().remove(&("AUTO_DEBIT", loan_id));
+
+        env.events().publish((AUTO_DEBIT_DISABLED, loan_id), ());
+        Ok(())
+    }
+
+    /// Cobra por débito automático as parcelas vencidas dos empréstimos informados
+    /// (keeper; qualquer conta pode chamar). Empréstimos inexistentes, inativos, sem
+    /// débito automático ou sem parcela vencida são ignorados; quando a autorização
+    /// ou o saldo do tomador não cobrem parcelas e gorjeta, nada é cobrado e o
+    /// evento `adb_fail` é emitido. Retorna os IDs cobrados.
+    pub fn collect_due_installments(env: Env, keeper: Address, loan_ids: Vec<u64>) -> Result<Vec<u64>, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        keeper.require_auth();
+
+        let contract = env.current_contract_address();
+        let current_time = env.ledger().timestamp();
+        let mut collected = Vec::new(&env);
+        for loan_id in loan_ids.iter() {
+            let loan: Option<Loan> = env.storage().persistent().get(&("LOAN", loan_id));
+            let tip: Option<i128> = env.storage().persistent().get(&("AUTO_DEBIT", loan_id));
+            let (mut loan, tip) = match (loan, tip) {
+                (Some(loan), Some(tip)) if loan.status == LoanStatus::Active => (loan, tip),
+                _ => continue,
+            };
+            if current_time < loan.next_payment_date {
+                continue;
+            }
+
+            let due = auto_debit_due(&env, &loan)?;
+            if due == 0 {
+                continue;
+            }
+
+            let token_client = token::Client::new(&env, &loan.asset);
+            let available = token_client.allowance(&loan.borrower, &contract)
+                .min(token_client.balance(&loan.borrower));
+            if available < due + tip {
+                env.events().publish((AUTO_DEBIT_FAILED, loan_id), (due + tip, available));
+                continue;
+            }
+
+            token_client.transfer_from(&contract, &loan.borrower, &contract, &due);
+            if tip > 0 {
+                token_client.transfer_from(&contract, &loan.borrower, &keeper, &tip);
+            }
+            Self::apply_payment(&env, &mut loan, due)?;
+
+            env.events().publish((AUTO_DEBIT_COLLECTED, loan_id), (due, tip));
+            collected.push_back(loan_id);
+        }
+        Ok(collected)
+    }
+
+    /// Coloca à venda a posição de um credor num empréstimo ativo
+    pub fn list_position(env: Env, seller: Address, loan_id: u64, price: i128) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        seller.require_auth();
+
+        let loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
+        if price <= 0 {
+            return Err(LoanError::InvalidAmount);
+        }
+
+        let shares = load_shares(&env, loan_id)?;
+        if !shares.iter().any(|share| share.lender == seller) {
+            return Err(LoanError::PositionNotFound);
+        }
+
+        let listing = PositionListing {
+            loan_id,
+            seller: seller.clone(),
+            price,
+            listed_at: env.ledger().timestamp(),
+        };
+        let key = ("LISTING", loan_id, seller.clone());
+        env.storage().persistent().set(&key, &listing);
+        bump_persistent(&env, &key);
+
+        emit_position_listed(&env, loan_id, seller, price);
+        Ok(())
+    }
+
+    pub fn cancel_listing(env: Env, seller: Address, loan_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        seller.require_auth();
+
+        let key = ("LISTING", loan_id, seller.clone());
+        if !env.storage().persistent().has(&key) {
+            return Err(LoanError::ListingNotFound);
+        }
+        env.storage().persistent().remove(&key);
+
+        emit_position_unlisted(&env, loan_id, seller);
+        Ok(())
+    }
+
+    /// Compra atomicamente uma posição listada: o preço vai do comprador ao vendedor
+    /// e as próximas parcelas passam a ser pagas ao comprador.
+    pub fn buy_position(env: Env, buyer: Address, loan_id: u64, seller: Address) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        buyer.require_auth();
+
+        let listing: PositionListing = env.storage()
+            .persistent()
+            .get(&("LISTING", loan_id, seller.clone()))
+            .ok_or(LoanError::ListingNotFound)?;
+
+        if buyer == seller {
+            return Err(LoanError::BuyerIsSeller);
+        }
 
-        let card: InvestmentCard = env.storage()
+        let mut loan: Loan = env.storage()
             .persistent()
-            .get(&("INV_CARD", card_id))
-            .expect("Card not found");
-        
-        assert!(card.is_active, "Card is not active");
-        assert!(amount >= card.min_amount && amount <= card.max_amount, "Amount out of range");
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
 
-        let app_id: u64 = env.storage().instance().get(&"NEXT_APP_ID").unwrap();
-        
-        let application = LoanApplication {
-            id: app_id,
-            card_id,
-            card_type: CardType::Investment,
-            applicant: borrower,
-            amount,
-            status: ApplicationStatus::Pending,
-            created_at: env.ledger().timestamp(),
-        };
+        let token_client = token::Client::new(&env, &loan.asset);
+        token_client.transfer(&buyer, &seller, &listing.price);
+
+        let mut shares = load_shares(&env, loan_id)?;
+
+        let seller_index = shares
+            .iter()
+            .position(|share| share.lender == seller)
+            .ok_or(LoanError::PositionNotFound)? as u32;
+        let position = shares.get(seller_index).unwrap();
+        shares.remove(seller_index);
+
+        // Se o comprador já tem posição no empréstimo, as duas são somadas
+        let mut merged = false;
+        for index in 0..shares.len() {
+            let mut share = shares.get(index).unwrap();
+            if share.lender == buyer {
+                share.amount += position.amount;
+                shares.set(index, share);
+                merged = true;
+                break;
+            }
+        }
+        if !merged {
+            shares.insert(seller_index, LoanShare {
+                lender: buyer.clone(),
+                amount: position.amount,
+            });
+        }
 
-        env.storage().persistent().set(&("APP", app_id), &application);
-        env.storage().instance().set(&"NEXT_APP_ID", &(app_id + 1));
+        loan.lender = lead_lender(&shares);
 
-        app_id
+        let mut history: Vec<OwnershipTransfer> = env.storage()
+            .persistent()
+            .get(&("OWNERSHIP", loan_id))
+            .unwrap_or(Vec::new(&env));
+        history.push_back(OwnershipTransfer {
+            from: seller.clone(),
+            to: buyer.clone(),
+            amount: position.amount,
+            price: listing.price,
+            timestamp: env.ledger().timestamp(),
+        });
+
+        env.storage().persistent().set(&("SHARES", loan_id), &shares);
+        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        env.storage().persistent().set(&("OWNERSHIP", loan_id), &history);
+        env.storage().persistent().remove(&("LISTING", loan_id, seller.clone()));
+        bump_loan(&env, loan_id);
+        index_remove(&env, &("LENDER_LOANS", seller.clone()), loan_id);
+        index_add(&env, &("LENDER_LOANS", buyer.clone()), loan_id);
+
+        emit_position_sold(&env, loan_id, seller, buyer, position.amount, listing.price);
+        Ok(())
     }
 
-    pub fn approve_application(
+    pub fn get_listing(env: Env, loan_id: u64, seller: Address) -> Option<PositionListing> {
+        bump_instance(&env);
+        read_persistent(&env, &("LISTING", loan_id, seller))
+    }
+
+    pub fn get_ownership_history(env: Env, loan_id: u64) -> Option<Vec<OwnershipTransfer>> {
+        bump_instance(&env);
+        read_persistent(&env, &("OWNERSHIP", loan_id))
+    }
+
+    /// Tomador propõe novas condições para o saldo remanescente do empréstimo
+    pub fn propose_restructuring(
         env: Env,
-        app_id: u64,
+        loan_id: u64,
+        interest_rate: u32,
         installments: u32,
         payment_dates: Vec<u64>,
-    ) -> u64 {
-        let mut application: LoanApplication = env.storage()
+    ) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let loan: Loan = env.storage()
             .persistent()
-            .get(&("APP", app_id))
-            .expect("Application not found");
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
 
-        let card: InvestmentCard = env.storage()
-            .persistent()
-            .get(&("INV_CARD", application.card_id))
-            .expect("Card not found");
+        loan.borrower.require_auth();
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
+        if installments == 0 || installments > MAX_INSTALLMENTS {
+            return Err(LoanError::InstallmentsOutOfRange);
+        }
 
-        card.investor.require_auth();
+        if let Some(existing) = Self::get_restructuring_proposal(env.clone(), loan_id) {
+            if existing.status == ProposalStatus::Pending {
+                return Err(LoanError::ProposalAlreadyPending);
+            }
+        }
 
-        let loan_id = Self::create_loan_internal(
-            &env,
-            application.applicant.clone(),
-            card.investor.clone(),
-            application.amount,
-            card.interest_rate,
+        let proposal = RestructuringProposal {
+            loan_id,
+            interest_rate,
             installments,
             payment_dates,
-        );
-
-        application.status = ApplicationStatus::Approved;
-        env.storage().persistent().set(&("APP", app_id), &application);
+            proposed_at: env.ledger().timestamp(),
+            status: ProposalStatus::Pending,
+        };
+        env.storage().persistent().set(&("RESTRUCT", loan_id), &proposal);
+        env.storage().persistent().remove(&("RESTR_APPROVALS", loan_id));
+        bump_persistent(&env, &("RESTRUCT", loan_id));
 
-        loan_id
+        emit_restructuring(&env, RESTRUCTURING_PROPOSED, &proposal);
+        Ok(())
     }
 
-    pub fn fund_request_card(
-        env: Env,
-        lender: Address,
-        card_id: u64,
-        interest_rate: u32,
-    ) -> u64 {
+    /// Um credor do empréstimo aceita a proposta. Ela só é aplicada quando todos os
+    /// detentores atuais de cotas tiverem aceitado: o saldo remanescente (principal e
+    /// juros vencidos) é recalculado com as novas condições e as anteriores ficam no
+    /// histórico. Retorna se a proposta foi aplicada.
+    pub fn accept_restructuring(env: Env, lender: Address, loan_id: u64) -> Result<bool, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
         lender.require_auth();
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
 
-        let mut card: RequestCard = env.storage()
+        let mut proposal: RestructuringProposal = env.storage()
             .persistent()
-            .get(&("REQ_CARD", card_id))
-            .expect("Card not found");
+            .get(&("RESTRUCT", loan_id))
+            .ok_or(LoanError::ProposalNotFound)?;
+        if proposal.status != ProposalStatus::Pending {
+            return Err(LoanError::ProposalNotPending);
+        }
 
-        assert!(card.is_active && !card.is_funded, "Card unavailable");
+        let shares: Vec<LoanShare> = env.storage()
+            .persistent()
+            .get(&("SHARES", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if !shares.iter().any(|share| share.lender == lender) {
+            return Err(LoanError::PositionNotFound);
+        }
 
-        let loan_id = Self::create_loan_internal(
+        let mut approvals: Vec<Address> = env.storage()
+            .persistent()
+            .get(&("RESTR_APPROVALS", loan_id))
+            .unwrap_or(Vec::new(&env));
+        if !approvals.contains(&lender) {
+            approvals.push_back(lender.clone());
+            env.events().publish((RESTRUCTURING_APPROVED, loan_id), lender);
+        }
+
+        // Cotas vendidas durante a votação passam a depender do aceite do comprador
+        if !shares.iter().all(|share| approvals.contains(&share.lender)) {
+            env.storage().persistent().set(&("RESTR_APPROVALS", loan_id), &approvals);
+            bump_loan(&env, loan_id);
+            return Ok(false);
+        }
+
+        let current_time = env.ledger().timestamp();
+        let schedule = load_schedule(&env, loan_id)?;
+
+        let mut terms_history: Vec<LoanTerms> = env.storage()
+            .persistent()
+            .get(&("TERMS_HISTORY", loan_id))
+            .unwrap_or(Vec::new(&env));
+        terms_history.push_back(LoanTerms {
+            interest_rate: loan.interest_rate,
+            installments: loan.installments,
+            total_due: loan.total_due,
+            schedule: schedule.clone(),
+            replaced_at: current_time,
+        });
+
+        let remaining = outstanding_principal(&env, &loan)? + overdue_interest(&env, &loan)?;
+        let new_installments = build_schedule(
             &env,
-            card.borrower.clone(),
-            lender,
-            card.requested_amount,
-            interest_rate,
-            card.desired_installments,
-            card.preferred_payment_dates.clone(),
-        );
+            remaining,
+            proposal.interest_rate,
+            &loan.amortization,
+            proposal.installments,
+            &proposal.payment_dates,
+            current_time,
+        )?;
+
+        // Parcelas já quitadas são mantidas; as novas continuam a numeração
+        let mut new_schedule = schedule.slice(0..loan.paid_installments);
+        let mut new_total = 0;
+        for mut installment in new_installments.iter() {
+            installment.number += loan.paid_installments;
+            new_total += installment.amount;
+            new_schedule.push_back(installment);
+        }
 
-        card.is_funded = true;
-        env.storage().persistent().set(&("REQ_CARD", card_id), &card);
+        let first = new_schedule.get(loan.paid_installments).unwrap();
+        loan.interest_rate = proposal.interest_rate;
+        loan.installments = loan.paid_installments + proposal.installments;
+        loan.installment_amount = first.amount;
+        loan.total_due = loan.total_paid + new_total;
+        loan.current_installment_paid = 0;
+        loan.next_payment_date = first.due_date;
+        loan.payment_dates = proposal.payment_dates.clone();
+
+        proposal.status = ProposalStatus::Accepted;
+
+        env.storage().persistent().set(&("SCHEDULE", loan_id), &new_schedule);
+        env.storage().persistent().set(&("TERMS_HISTORY", loan_id), &terms_history);
+        env.storage().persistent().set(&("RESTRUCT", loan_id), &proposal);
+        env.storage().persistent().remove(&("RESTR_APPROVALS", loan_id));
+        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        bump_loan(&env, loan_id);
 
-        loan_id
+        let credit_score: Address = env.storage()
+            .instance()
+            .get(&"CREDIT_SCORE")
+            .ok_or(LoanError::NotInitialized)?;
+        CreditScoreClient::new(&env, &credit_score).record_renegotiation(&loan.borrower, &loan_id);
+
+        emit_restructuring(&env, RESTRUCTURING_ACCEPTED, &proposal);
+        Ok(true)
     }
 
-    fn create_loan_internal(
-        env: &Env,
-        borrower: Address,
-        lender: Address,
-        amount: i128,
-        interest_rate: u32,
-        installments: u32,
-        payment_dates: Vec<u64>,
-    ) -> u64 {
-        let loan_id: u64 = env.storage().instance().get(&"NEXT_LOAN_ID").unwrap();
+    /// Qualquer detentor de cotas pode recusar a proposta, já que ela exige aceite unânime
+    pub fn reject_restructuring(env: Env, lender: Address, loan_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        lender.require_auth();
 
-        let total_with_interest = amount + (amount * interest_rate as i128) / 10000;
-        let installment_amount = total_with_interest / installments as i128;
+        let shares: Vec<LoanShare> = env.storage()
+            .persistent()
+            .get(&("SHARES", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if !shares.iter().any(|share| share.lender == lender) {
+            return Err(LoanError::PositionNotFound);
+        }
 
-        let loan = Loan {
-            id: loan_id,
-            borrower: borrower.clone(),
-            lender: lender.clone(),
-            amount,
-            interest_rate,
-            installments,
-            installment_amount,
-            paid_installments: 0,
-            total_paid: 0,
-            status: LoanStatus::Active,
-            created_at: env.ledger().timestamp(),
-            next_payment_date: payment_dates.get(0).unwrap_or(env.ledger().timestamp() + 30 * DAY_IN_SECONDS),
-            payment_dates,
-        };
+        let mut proposal: RestructuringProposal = env.storage()
+            .persistent()
+            .get(&("RESTRUCT", loan_id))
+            .ok_or(LoanError::ProposalNotFound)?;
+        if proposal.status != ProposalStatus::Pending {
+            return Err(LoanError::ProposalNotPending);
+        }
 
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(env, &token_address);
-        token_client.transfer(&lender, &borrower, &amount);
+        proposal.status = ProposalStatus::Rejected;
+        env.storage().persistent().set(&("RESTRUCT", loan_id), &proposal);
+        env.storage().persistent().remove(&("RESTR_APPROVALS", loan_id));
+        bump_persistent(&env, &("RESTRUCT", loan_id));
 
-        let governance: Address = env.storage().instance().get(&"GOVERNANCE").unwrap();
-        let governance_fee = (amount * 50) / 10000;
-        token_client.transfer(&lender, &governance, &governance_fee);
+        emit_restructuring(&env, RESTRUCTURING_REJECTED, &proposal);
+        Ok(())
+    }
 
-        env.storage().persistent().set(&("LOAN", loan_id), &loan);
-        env.storage().instance().set(&"NEXT_LOAN_ID", &(loan_id + 1));
+    pub fn get_restructuring_proposal(env: Env, loan_id: u64) -> Option<RestructuringProposal> {
+        bump_instance(&env);
+        read_persistent(&env, &("RESTRUCT", loan_id))
+    }
 
-        emit_loan_created(env, loan_id, borrower, lender, amount);
-        loan_id
+    /// Credores que já aceitaram a proposta pendente
+    pub fn get_restructuring_approvals(env: Env, loan_id: u64) -> Vec<Address> {
+        bump_instance(&env);
+        read_persistent(&env, &("RESTR_APPROVALS", loan_id))
+            .unwrap_or(Vec::new(&env))
     }
 
-    pub fn make_payment(env: Env, loan_id: u64) -> bool {
-        let mut loan: Loan = env.storage()
+    pub fn get_terms_history(env: Env, loan_id: u64) -> Option<Vec<LoanTerms>> {
+        bump_instance(&env);
+        read_persistent(&env, &("TERMS_HISTORY", loan_id))
+    }
+
+    pub fn mark_as_defaulted(env: Env, loan_id: u64) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        let loan: Loan = env.storage()
             .persistent()
             .get(&("LOAN", loan_id))
-            .expect("Loan not found");
+            .ok_or(LoanError::LoanNotFound)?;
 
-        loan.borrower.require_auth();
-        assert_eq!(loan.status, LoanStatus::Active, "Loan not active");
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
+        // A carência não corre durante pausas totais
+        let now = unpaused_time(&pause_periods(&env)?, loan.next_payment_date, env.ledger().timestamp());
+        if now <= loan.next_payment_date + Self::get_grace_period(env.clone()) {
+            return Err(LoanError::GracePeriodNotExpired);
+        }
+
+        Self::default_loan(&env, loan)
+    }
+
+    /// Verifica os empréstimos informados (keeper; qualquer conta pode chamar). Na
+    /// primeira verificação depois do vencimento de uma parcela emite `overdue`;
+    /// passada a carência, marca o empréstimo como inadimplente com os mesmos efeitos
+    /// de `mark_as_defaulted`. IDs inexistentes ou inativos são ignorados, e um empréstimo
+    /// cujo default falha emite `dflt_fail` com o código do erro e continua `Active`, sem
+    /// interromper os demais. Retorna os IDs que entraram em inadimplência.
+    pub fn process_overdue(env: Env, loan_ids: Vec<u64>) -> Result<Vec<u64>, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
 
         let current_time = env.ledger().timestamp();
-        let on_time = current_time <= loan.next_payment_date + DAY_IN_SECONDS;
+        let grace_period = Self::get_grace_period(env.clone());
+        let periods = pause_periods(&env)?;
+        let mut defaulted = Vec::new(&env);
+        for loan_id in loan_ids.iter() {
+            let loan: Option<Loan> = env.storage().persistent().get(&("LOAN", loan_id));
+            let loan = match loan {
+                Some(loan) if loan.status == LoanStatus::Active => loan,
+                _ => continue,
+            };
+            if current_time <= loan.next_payment_date {
+                continue;
+            }
+
+            // Um aviso por parcela, mesmo que o keeper passe várias vezes
+            let installment_number = loan.paid_installments + 1;
+            let notice_key = ("OVERDUE_NOTICE", loan_id);
+            let notified: u32 = env.storage().persistent().get(&notice_key).unwrap_or(0);
+            if notified < installment_number {
+                env.storage().persistent().set(&notice_key, &installment_number);
+                bump_persistent(&env, &notice_key);
+                env.events().publish(
+                    (PAYMENT_OVERDUE, loan_id),
+                    (installment_number, loan.next_payment_date),
+                );
+            }
+
+            if unpaused_time(&periods, loan.next_payment_date, current_time) > loan.next_payment_date + grace_period {
+                // Uma falha nos outros contratos deixa só este empréstimo para a próxima passada
+                match Self::default_loan(&env, loan) {
+                    Ok(()) => defaulted.push_back(loan_id),
+                    Err(error) => env.events().publish((DEFAULT_FAILED, loan_id), error as u32),
+                }
+            }
+        }
+        Ok(defaulted)
+    }
 
-        let token_address: Address = env.storage().instance().get(&"TOKEN").unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&loan.borrower, &loan.lender, &loan.installment_amount);
+    /// Marca o empréstimo como inadimplente: registra o default no credit score,
+    /// executa a garantia e abre o claim no fundo de proteção. As chamadas aos outros
+    /// contratos vêm antes de qualquer escrita local; se uma falhar, retorna
+    /// `DefaultNotRecorded` com o empréstimo intacto. Como `record_default` ignora
+    /// repetições, uma nova tentativa depois de o claim falhar não duplica o default.
+    fn default_loan(env: &Env, mut loan: Loan) -> Result<(), LoanError> {
+        let loan_id = loan.id;
+        let outstanding = outstanding_balance(&loan);
+
+        // A garantia é executada primeiro; o claim no fundo de proteção cobre só o
+        // principal que ela não alcançou
+        let collateral: Option<Collateral> = env.storage().persistent().get(&("COLLATERAL", loan_id));
+        let to_seize = collateral.map_or(0, |collateral| collateral.amount.min(outstanding));
+        let principal = (outstanding_principal(env, &loan)? - to_seize).max(0);
+
+        let credit_score: Address = env.storage()
+            .instance()
+            .get(&"CREDIT_SCORE")
+            .ok_or(LoanError::NotInitialized)?;
+        let recorded = CreditScoreClient::new(env, &credit_score).try_record_default(
+            &loan.borrower,
+            &loan_id,
+            &outstanding,
+        );
+        if !matches!(recorded, Ok(Ok(()))) {
+            return Err(LoanError::DefaultNotRecorded);
+        }
+        if principal > 0 {
+            let governance: Address = env.storage()
+                .instance()
+                .get(&"GOVERNANCE")
+                .ok_or(LoanError::NotInitialized)?;
+            let opened = GovernanceClient::new(env, &governance)
+                .try_open_claim(&loan_id, &loan.asset, &principal);
+            if !matches!(opened, Ok(Ok(()))) {
+                return Err(LoanError::DefaultNotRecorded);
+            }
+        }
 
-        loan.paid_installments += 1;
-        loan.total_paid += loan.installment_amount;
+        set_loan_status(env, &mut loan, LoanStatus::Defaulted);
+        seize_collateral(env, &mut loan)?;
+        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        bump_loan(env, loan_id);
+        // Após a execução da garantia os avalistas respondem só pelo saldo restante
+        update_guarantees(env, loan_id, outstanding_balance(&loan).min(loan.amount))?;
 
-        let payment = Payment {
-            loan_id,
-            installment_number: loan.paid_installments,
-            amount: loan.installment_amount,
-            paid_at: current_time,
-            was_on_time: on_time,
-        };
-        
-        let mut payments: Vec<Payment> = env.storage()
+        env.events().publish((LOAN_DEFAULTED, loan_id), outstanding);
+        Ok(())
+    }
+
+    /// Cobra dos avalistas de um empréstimo inadimplente as parcelas vencidas e não
+    /// pagas. Qualquer credor do empréstimo pode chamar; os avalistas são cobrados na
+    /// ordem em que foram nomeados, cada um até o que autorizou ao contrato
+    /// (`approve`) e tem em saldo. O valor recebido vai aos credores pelas cotas,
+    /// abate a dívida e reduz o claim aberto no fundo de proteção; se o claim já foi
+    /// pago, a cobrança se limita ao saldo que a cobertura não alcançou. Retorna o
+    /// total cobrado.
+    pub fn call_guarantors(env: Env, lender: Address, loan_id: u64) -> Result<i128, LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        lender.require_auth();
+
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if loan.status != LoanStatus::Defaulted {
+            return Err(LoanError::LoanNotDefaulted);
+        }
+
+        let shares: Vec<LoanShare> = env.storage()
+            .persistent()
+            .get(&("SHARES", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        if !shares.iter().any(|share| share.lender == lender) {
+            return Err(LoanError::PositionNotFound);
+        }
+
+        let guarantors: Vec<Address> = env.storage()
             .persistent()
-            .get(&("PAYMENTS", loan_id))
+            .get(&("GUARANTORS", loan_id))
             .unwrap_or(Vec::new(&env));
-        payments.push_back(payment);
-        env.storage().persistent().set(&("PAYMENTS", loan_id), &payments);
+        let limit = guarantee_limit(&env, &loan)?;
+        let due = missed_installments(&env, &loan)?.min(limit);
+        if guarantors.is_empty() || due == 0 {
+            return Err(LoanError::NoGuaranteeDue);
+        }
 
-        if loan.paid_installments < loan.installments {
-            let next_idx = loan.paid_installments as u32;
-            loan.next_payment_date = loan.payment_dates
-                .get(next_idx)
-                .unwrap_or(current_time + 30 * DAY_IN_SECONDS);
-        } else {
-            loan.status = LoanStatus::Completed;
-            emit_loan_completed(&env, loan_id, loan.borrower.clone(), loan.total_paid);
+        let token_client = token::Client::new(&env, &loan.asset);
+        let contract = env.current_contract_address();
+        let mut collected = 0;
+        for guarantor in guarantors.iter() {
+            let available = token_client.allowance(&guarantor, &contract)
+                .min(token_client.balance(&guarantor));
+            let amount = (due - collected).min(available);
+            if amount <= 0 {
+                continue;
+            }
+
+            token_client.transfer_from(&contract, &guarantor, &contract, &amount);
+            collected += amount;
+            env.events().publish((GUARANTEE_CALLED, loan_id), (guarantor, amount));
+            if collected == due {
+                break;
+            }
         }
 
-        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+        if collected > 0 {
+            distribute_to_lenders(&env, &token_client, &loan, collected)?;
+            loan.total_paid += collected;
+            env.storage().persistent().set(&("LOAN", loan_id), &loan);
+            bump_loan(&env, loan_id);
+
+            // O que os avalistas pagaram deixa de ser coberto pelo fundo de proteção
+            if let Some(claim) = protection_claim(&env, loan_id)? {
+                if claim.status == ClaimStatus::Open {
+                    let governance: Address = env.storage()
+                        .instance()
+                        .get(&"GOVERNANCE")
+                        .ok_or(LoanError::NotInitialized)?;
+                    GovernanceClient::new(&env, &governance).reduce_claim(&loan_id, &collected);
+                }
+            }
+            update_guarantees(&env, loan_id, (limit - collected).min(loan.amount))?;
+        }
+        Ok(collected)
+    }
 
-        emit_payment_made(&env, loan_id, loan.borrower.clone(), loan.installment_amount, loan.paid_installments, on_time);
+    /// Renova o TTL da instância e das entradas dos empréstimos e cards informados.
+    /// Qualquer conta (ex.: um keeper) pode chamar; IDs inexistentes são ignorados.
+    pub fn extend_ttl(env: Env, loan_ids: Vec<u64>, card_ids: Vec<u64>) {
+        bump_instance(&env);
+        for loan_id in loan_ids.iter() {
+            bump_loan(&env, loan_id);
+        }
+        for card_id in card_ids.iter() {
+            bump_card(&env, card_id);
+        }
+    }
 
-        loan.status == LoanStatus::Completed
+    pub fn get_loan(env: Env, loan_id: u64) -> Option<Loan> {
+        bump_instance(&env);
+        read_persistent(&env, &("LOAN", loan_id))
     }
 
-    pub fn mark_as_defaulted(env: Env, loan_id: u64) {
-        let admin: Address = env.storage().instance().get(&"ADMIN").unwrap();
-        admin.require_auth();
+    /// Empréstimos do tomador, paginados (`limit` até `MAX_PAGE_SIZE`) e opcionalmente filtrados por status
+    pub fn list_loans_by_borrower(
+        env: Env,
+        borrower: Address,
+        status: Option<LoanStatus>,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Loan> {
+        bump_instance(&env);
+        let ids = index_get(&env, &("BORROWER_LOANS", borrower));
+        paginate_loans(&env, ids, status, start, limit)
+    }
 
-        let mut loan: Loan = env.storage()
-            .persistent()
-            .get(&("LOAN", loan_id))
-            .expect("Loan not found");
+    /// Empréstimos em que o endereço detém uma posição de credor
+    pub fn list_loans_by_lender(
+        env: Env,
+        lender: Address,
+        status: Option<LoanStatus>,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Loan> {
+        bump_instance(&env);
+        let ids = index_get(&env, &("LENDER_LOANS", lender));
+        paginate_loans(&env, ids, status, start, limit)
+    }
 
+    pub fn list_loans_by_status(env: Env, status: LoanStatus, start: u32, limit: u32) -> Vec<Loan> {
+        bump_instance(&env);
+        let ids = index_get(&env, &("STATUS_LOANS", status));
+        paginate_loans(&env, ids, None, start, limit)
+    }
+
+    pub fn list_active_investment_cards(env: Env, start: u32, limit: u32) -> Vec<InvestmentCard> {
+        bump_instance(&env);
+        let mut page = Vec::new(&env);
+        let mut skipped = 0;
+        for id in index_get(&env, &"ACTIVE_INV_CARDS").iter() {
+            if page.len() >= limit.min(MAX_PAGE_SIZE) {
+                break;
+            }
+            let card: InvestmentCard = match read_persistent(&env, &("INV_CARD", id)) {
+                Some(card) => card,
+                None => continue,
+            };
+            if skipped < start {
+                skipped += 1;
+                continue;
+            }
+            page.push_back(card);
+        }
+        page
+    }
+
+    /// Cards de solicitação ainda aceitando aportes (prazo de captação não encerrado)
+    pub fn list_open_request_cards(env: Env, start: u32, limit: u32) -> Vec<RequestCard> {
+        bump_instance(&env);
         let current_time = env.ledger().timestamp();
-        let grace_period = 7 * DAY_IN_SECONDS;
+        let mut page = Vec::new(&env);
+        let mut skipped = 0;
+        for id in index_get(&env, &"OPEN_REQ_CARDS").iter() {
+            if page.len() >= limit.min(MAX_PAGE_SIZE) {
+                break;
+            }
+            let card: RequestCard = match read_persistent(&env, &("REQ_CARD", id)) {
+                Some(card) => card,
+                None => continue,
+            };
+            if current_time > card.funding_deadline {
+                continue;
+            }
+            if skipped < start {
+                skipped += 1;
+                continue;
+            }
+            page.push_back(card);
+        }
+        page
+    }
 
-        assert!(
-            current_time > loan.next_payment_date + grace_period,
-            "Grace period not expired"
-        );
+    pub fn get_application(env: Env, app_id: u64) -> Option<LoanApplication> {
+        bump_instance(&env);
+        read_persistent(&env, &("APP", app_id))
+    }
 
-        loan.status = LoanStatus::Defaulted;
-        env.storage().persistent().set(&("LOAN", loan_id), &loan);
+    /// Status efetivo: aplicações pendentes com prazo vencido são reportadas como `Expired`
+    pub fn get_application_status(env: Env, app_id: u64) -> Option<ApplicationStatus> {
+        bump_instance(&env);
+        let application: LoanApplication = read_persistent(&env, &("APP", app_id))?;
+
+        if application.status == ApplicationStatus::Pending
+            && env.ledger().timestamp() > application.expires_at
+        {
+            return Some(ApplicationStatus::Expired);
+        }
+        Some(application.status)
     }
 
-    pub fn get_loan(env: Env, loan_id: u64) -> Option<Loan> {
-        env.storage().persistent().get(&("LOAN", loan_id))
+    pub fn get_card_applications(env: Env, card_id: u64) -> Vec<u64> {
+        bump_instance(&env);
+        read_persistent(&env, &("CARD_APPS", card_id))
+            .unwrap_or(Vec::new(&env))
     }
 
     pub fn get_investment_card(env: Env, card_id: u64) -> Option<InvestmentCard> {
-        env.storage().persistent().get(&("INV_CARD", card_id))
+        bump_instance(&env);
+        read_persistent(&env, &("INV_CARD", card_id))
     }
 
     pub fn get_request_card(env: Env, card_id: u64) -> Option<RequestCard> {
-        env.storage().persistent().get(&("REQ_CARD", card_id))
+        bump_instance(&env);
+        read_persistent(&env, &("REQ_CARD", card_id))
+    }
+
+    pub fn get_schedule(env: Env, loan_id: u64) -> Option<Vec<Installment>> {
+        bump_instance(&env);
+        read_persistent(&env, &("SCHEDULE", loan_id))
+    }
+
+    /// Saldo para quitação: parcelas em aberto mais encargos de atraso já devidos
+    pub fn get_outstanding_balance(env: Env, loan_id: u64) -> Result<i128, LoanError> {
+        bump_instance(&env);
+        let loan: Loan = read_persistent(&env, &("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        Ok(outstanding_balance(&loan) + late_charges_due(&env, &loan)?)
+    }
+
+    pub fn get_late_charges(env: Env, loan_id: u64) -> Result<i128, LoanError> {
+        bump_instance(&env);
+        let loan: Loan = read_persistent(&env, &("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+        late_charges_due(&env, &loan)
+    }
+
+    pub fn get_loan_shares(env: Env, loan_id: u64) -> Option<Vec<LoanShare>> {
+        bump_instance(&env);
+        read_persistent(&env, &("SHARES", loan_id))
+    }
+
+    pub fn get_funding_commitments(env: Env, card_id: u64) -> Option<Vec<FundingCommitment>> {
+        bump_instance(&env);
+        read_persistent(&env, &("REQ_FUNDS", card_id))
     }
 
     pub fn get_payment_history(env: Env, loan_id: u64) -> Option<Vec<Payment>> {
-        env.storage().persistent().get(&("PAYMENTS", loan_id))
+        bump_instance(&env);
+        read_persistent(&env, &("PAYMENTS", loan_id))
+    }
+
+    pub fn get_collateral_terms(env: Env, card_id: u64) -> Option<CollateralTerms> {
+        bump_instance(&env);
+        read_persistent(&env, &("COLLATERAL_TERMS", card_id))
+    }
+
+    pub fn get_collateral(env: Env, loan_id: u64) -> Option<Collateral> {
+        bump_instance(&env);
+        read_persistent(&env, &("COLLATERAL", loan_id))
+    }
+
+    pub fn get_guarantors(env: Env, loan_id: u64) -> Option<Vec<Address>> {
+        bump_instance(&env);
+        read_persistent(&env, &("GUARANTORS", loan_id))
+    }
+
+    /// Gorjeta do keeper quando o débito automático está ativo
+    pub fn get_auto_debit(env: Env, loan_id: u64) -> Option<i128> {
+        bump_instance(&env);
+        read_persistent(&env, &("AUTO_DEBIT", loan_id))
     }
 
-    pub fn cancel_card(env: Env, card_id: u64, is_investment: bool) {
+    pub fn cancel_card(env: Env, card_id: u64, is_investment: bool) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
         if is_investment {
             let mut card: InvestmentCard = env.storage()
                 .persistent()
                 .get(&("INV_CARD", card_id))
-                .expect("Card not found");
+                .ok_or(LoanError::CardNotFound)?;
             card.investor.require_auth();
+
+            // Devolve ao investidor o capital que ainda não foi emprestado
+            if card.escrowed_amount > 0 {
+                let token_client = token::Client::new(&env, &card.asset);
+                token_client.transfer(&env.current_contract_address(), &card.investor, &card.escrowed_amount);
+            }
+
             card.is_active = false;
+            card.escrowed_amount = 0;
             env.storage().persistent().set(&("INV_CARD", card_id), &card);
+            bump_card(&env, card_id);
+            index_remove(&env, &"ACTIVE_INV_CARDS", card_id);
         } else {
             let mut card: RequestCard = env.storage()
                 .persistent()
                 .get(&("REQ_CARD", card_id))
-                .expect("Card not found");
+                .ok_or(LoanError::CardNotFound)?;
             card.borrower.require_auth();
+
+            // Devolve aos credores as contribuições de um card ainda não financiado
+            if !card.is_funded {
+                Self::refund_commitments(&env, &mut card)?;
+            }
+
             card.is_active = false;
             env.storage().persistent().set(&("REQ_CARD", card_id), &card);
+            bump_card(&env, card_id);
+            index_remove(&env, &"OPEN_REQ_CARDS", card_id);
         }
+        Ok(())
     }
 
     pub fn register_pix_payment(
@@ -568,13 +3828,53 @@ impl LoanContract {
         amount: i128,
         pix_type: PixType,
         related_loan_id: u64,
-    ) {
-        let admin: Address = env.storage().instance().get(&"ADMIN").unwrap();
+    ) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
         admin.require_auth();
+
+        if env.storage().persistent().has(&("PIX_PAYMENT", payment_id.clone())) {
+            return Err(LoanError::DuplicatePixPayment);
+        }
+        if env.storage().persistent().has(&("PIX_ORDER", order_id.clone())) {
+            return Err(LoanError::DuplicatePixOrder);
+        }
+
+        // Desembolsos e pagamentos seguem o ativo do empréstimo relacionado
+        let asset = match pix_type {
+            PixType::Investment => default_asset(&env)?,
+            PixType::CreditPayout | PixType::Repayment => {
+                let loan: Loan = env.storage()
+                    .persistent()
+                    .get(&("LOAN", related_loan_id))
+                    .ok_or(LoanError::LoanNotFound)?;
+
+                // O desembolso é único por empréstimo e precisa cobrir exatamente o principal
+                if pix_type == PixType::CreditPayout {
+                    if loan.status != LoanStatus::Pending {
+                        return Err(LoanError::LoanNotPending);
+                    }
+                    if amount != loan.amount {
+                        return Err(LoanError::InvalidAmount);
+                    }
+                    if env.storage().persistent().has(&("LOAN_PAYOUT", related_loan_id)) {
+                        return Err(LoanError::DuplicatePixPayment);
+                    }
+                    env.storage().persistent().set(&("LOAN_PAYOUT", related_loan_id), &payment_id);
+                    bump_persistent(&env, &("LOAN_PAYOUT", related_loan_id));
+                }
+                loan.asset
+            }
+        };
         
         let pix_payment = PixPayment {
             payment_id: payment_id.clone(),
             order_id: order_id.clone(),
+            asset,
             amount,
             status: PixStatus::Pending,
             created_at: env.ledger().timestamp(),
@@ -583,59 +3883,213 @@ impl LoanContract {
             related_loan_id,
         };
         
-        env.storage().persistent().set(&("PIX_PAYMENT", payment_id), &pix_payment);
-        env.storage().persistent().set(&("PIX_ORDER", order_id), &payment_id);
+        env.storage().persistent().set(&("PIX_PAYMENT", payment_id.clone()), &pix_payment);
+        env.storage().persistent().set(&("PIX_ORDER", order_id.clone()), &payment_id);
+        bump_persistent(&env, &("PIX_PAYMENT", payment_id.clone()));
+        bump_persistent(&env, &("PIX_ORDER", order_id));
+        add_pix_status_change(&env, &pix_payment);
+
+        emit_pix_status(&env, PIX_REGISTERED, &pix_payment);
+        Ok(())
     }
     
+    /// Aplica um status informado pelo provedor PIX. Repetir o status atual não tem
+    /// efeito (webhooks reenviados não processam o pagamento duas vezes); transições
+    /// fora da tabela de `pix_transition_allowed` falham com `InvalidPixTransition`.
     pub fn update_pix_payment_status(
         env: Env,
         payment_id: String,
         new_status: PixStatus,
-    ) {
-        let admin: Address = env.storage().instance().get(&"ADMIN").unwrap();
+    ) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
         admin.require_auth();
         
-        let mut payment: PixPayment = env.storage()
-            .persistent()
-            .get(&("PIX_PAYMENT", payment_id.clone()))
-            .expect("PIX payment not found");
+        let payment = load_pix_payment(&env, payment_id).ok_or(LoanError::PixPaymentNotFound)?;
+
+        Self::apply_pix_status(&env, payment, new_status)?;
+        Ok(())
+    }
+
+    /// Aplica um status atestado por um oráculo PIX registrado. Qualquer conta pode
+    /// retransmitir o atestado: o oráculo assina o XDR de `(endereço deste contrato,
+    /// attestation)`, então um atestado emitido para outra implantação não vale aqui, e o
+    /// atestado precisa corresponder ao pedido e ao valor registrados.
+    ///
+    /// Assinatura inválida não vira `LoanError`: `ed25519_verify` aborta a transação com
+    /// erro do host. `timestamp` não é validado; retransmissões antigas são contidas pelas
+    /// transições de status permitidas, não pelo horário.
+    pub fn submit_pix_attestation(
+        env: Env,
+        attestation: PixAttestation,
+        oracle: BytesN<32>,
+        signature: BytesN<64>,
+    ) -> Result<(), LoanError> {
+        bump_instance(&env);
+        ensure_not_paused(&env, false)?;
+        if !Self::get_pix_oracles(env.clone()).contains(&oracle) {
+            return Err(LoanError::UnknownPixOracle);
+        }
+        let payload = (env.current_contract_address(), attestation.clone()).to_xdr(&env);
+        env.crypto().ed25519_verify(&oracle, &payload, &signature);
+
+        let payment = load_pix_payment(&env, attestation.payment_id.clone())
+            .ok_or(LoanError::PixPaymentNotFound)?;
+        if payment.order_id != attestation.order_id || payment.amount != attestation.amount {
+            return Err(LoanError::PixAttestationMismatch);
+        }
+
+        if Self::apply_pix_status(&env, payment, attestation.status.clone())? {
+            env.events().publish(
+                (PIX_ATTESTED,),
+                PixAttestedEvent {
+                    payment_id: attestation.payment_id,
+                    status: attestation.status,
+                    oracle,
+                    attested_at: attestation.timestamp,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Registra a chave pública ed25519 de um oráculo PIX autorizado a atestar status
+    pub fn add_pix_oracle(env: Env, public_key: BytesN<32>) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut oracles = Self::get_pix_oracles(env.clone());
+        if !oracles.contains(&public_key) {
+            oracles.push_back(public_key.clone());
+            env.storage().instance().set(&"PIX_ORACLES", &oracles);
+            env.events().publish((PIX_ORACLE_ADDED,), public_key);
+        }
+        Ok(())
+    }
+
+    pub fn remove_pix_oracle(env: Env, public_key: BytesN<32>) -> Result<(), LoanError> {
+        bump_instance(&env);
+        let admin: Address = env.storage()
+            .instance()
+            .get(&"ADMIN")
+            .ok_or(LoanError::NotInitialized)?;
+        admin.require_auth();
+
+        let mut oracles = Self::get_pix_oracles(env.clone());
+        let index = oracles.first_index_of(&public_key).ok_or(LoanError::UnknownPixOracle)?;
+        oracles.remove(index);
+        env.storage().instance().set(&"PIX_ORACLES", &oracles);
+        env.events().publish((PIX_ORACLE_REMOVED,), public_key);
+        Ok(())
+    }
+
+    pub fn get_pix_oracles(env: Env) -> Vec<BytesN<32>> {
+        bump_instance(&env);
+        env.storage().instance().get(&"PIX_ORACLES").unwrap_or(Vec::new(&env))
+    }
+
+    /// Aplica a transição e dispara o efeito de `Completed` (ou o cancelamento do
+    /// empréstimo quando o desembolso não se conclui). Retorna `false` quando o
+    /// status já era o atual (webhook ou atestado reenviado), sem nenhum efeito.
+    fn apply_pix_status(env: &Env, mut payment: PixPayment, new_status: PixStatus) -> Result<bool, LoanError> {
+        if payment.status == new_status {
+            return Ok(false);
+        }
+        if !pix_transition_allowed(&payment.status, &new_status) {
+            return Err(LoanError::InvalidPixTransition);
+        }
             
-        payment.status = new_status;
+        payment.status = new_status.clone();
         payment.updated_at = env.ledger().timestamp();
         
-        env.storage().persistent().set(&("PIX_PAYMENT", payment_id), &payment);
+        let key = ("PIX_PAYMENT", payment.payment_id.clone());
+        env.storage().persistent().set(&key, &payment);
+        bump_persistent(env, &key);
+        add_pix_status_change(env, &payment);
+        emit_pix_status(env, PIX_STATUS_CHANGED, &payment);
         
-        // If payment is completed, trigger corresponding action
-        if new_status == PixStatus::Completed {
-            Self::handle_completed_pix_payment(&env, payment);
+        match new_status {
+            PixStatus::Completed => Self::handle_completed_pix_payment(env, payment)?,
+            PixStatus::Failed | PixStatus::Expired | PixStatus::Cancelled
+                if payment.pix_type == PixType::CreditPayout =>
+            {
+                Self::cancel_pending_loan(env, payment.related_loan_id)?
+            }
+            _ => {}
         }
+        Ok(true)
     }
     
-    fn handle_completed_pix_payment(env: &Env, payment: PixPayment) {
+    fn handle_completed_pix_payment(env: &Env, payment: PixPayment) -> Result<(), LoanError> {
         match payment.pix_type {
             PixType::Investment => {
                 // Investment PIX completed - funds are now available
                 // You might want to update investment status here
             },
             PixType::CreditPayout => {
-                // Credit payout PIX completed - borrower received funds
-                // You might want to update loan status here
+                // Tomador recebeu o PIX: o empréstimo começa a contar a partir de agora
+                Self::activate_loan(env, payment.related_loan_id)?;
             },
             PixType::Repayment => {
-                // Repayment PIX completed - process loan payment
-                let _ = Self::make_payment(env, payment.related_loan_id);
+                Self::settle_pix_repayment(env, payment.related_loan_id, payment.amount)?;
             },
         }
+        Ok(())
+    }
+
+    /// PIX de pagamento confirmado: os tokens saem da carteira do tomador pela
+    /// autorização (`approve`) dada antes ao contrato, de modo que o admin ou quem
+    /// retransmite o atestado do oráculo não precisa da assinatura do tomador
+    fn settle_pix_repayment(env: &Env, loan_id: u64, amount: i128) -> Result<(), LoanError> {
+        let mut loan: Loan = env.storage()
+            .persistent()
+            .get(&("LOAN", loan_id))
+            .ok_or(LoanError::LoanNotFound)?;
+
+        if loan.status != LoanStatus::Active {
+            return Err(LoanError::LoanNotActive);
+        }
+        if amount <= 0 {
+            return Err(LoanError::InvalidAmount);
+        }
+        if amount > outstanding_balance(&loan) + late_charges_due(env, &loan)? {
+            return Err(LoanError::AmountExceedsOutstanding);
+        }
+
+        let token_client = token::Client::new(env, &loan.asset);
+        let contract = env.current_contract_address();
+        let available = token_client.allowance(&loan.borrower, &contract)
+            .min(token_client.balance(&loan.borrower));
+        if available < amount {
+            return Err(LoanError::InsufficientAllowance);
+        }
+
+        token_client.transfer_from(&contract, &loan.borrower, &contract, &amount);
+        Self::apply_payment(env, &mut loan, amount)
     }
     
     pub fn get_pix_payment(env: Env, payment_id: String) -> Option<PixPayment> {
-        env.storage().persistent().get(&("PIX_PAYMENT", payment_id))
+        bump_instance(&env);
+        load_pix_payment(&env, payment_id)
+    }
+    
+    pub fn get_pix_status_history(env: Env, payment_id: String) -> Vec<PixStatusChange> {
+        bump_instance(&env);
+        read_persistent(&env, &("PIX_HISTORY", payment_id))
+            .unwrap_or(Vec::new(&env))
     }
     
     pub fn get_pix_payment_by_order(env: Env, order_id: String) -> Option<PixPayment> {
-        let payment_id: String = env.storage()
-            .persistent()
-            .get(&("PIX_ORDER", order_id))?;
+        bump_instance(&env);
+        let payment_id: String = read_persistent(&env, &("PIX_ORDER", order_id))?;
         Self::get_pix_payment(env, payment_id)
     }
 }
\ No newline at end of file
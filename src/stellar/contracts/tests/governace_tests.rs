@@ -1,12 +1,16 @@
 #![cfg(test)]
 
-use soroban_sdk::{testutils::Address as _, token, Address, Env};
-use mithril_contracts::governance::{GovernanceContract, GovernanceContractClient};
+use soroban_sdk::{testutils::{Address as _, Ledger}, token, vec, Address, Env};
+use mithril_contracts::governance::{ClaimStatus, GovernanceContract, GovernanceContractClient, GovernanceError, PausePeriod, PauseScope, ProtectionFund};
 
 fn create_token_contract<'a>(env: &Env, admin: &Address) -> token::Client<'a> {
     token::Client::new(env, &env.register_stellar_asset_contract(admin.clone()))
 }
 
+fn mint(env: &Env, token: &Address, to: &Address, amount: i128) {
+    token::StellarAssetClient::new(env, token).mint(to, &amount);
+}
+
 fn create_governance_contract<'a>(env: &Env) -> (Address, GovernanceContractClient<'a>) {
     let contract_id = env.register_contract(None, GovernanceContract);
     let client = GovernanceContractClient::new(env, &contract_id);
@@ -36,6 +40,26 @@ fn test_initialize() {
     assert_eq!(fees.gas_fee, 10);
 }
 
+#[test]
+fn test_initialize_twice_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    
+    let (_, client) = create_governance_contract(&env);
+    client.initialize(&admin, &token_client.address, &50, &10);
+    
+    let attacker = Address::generate(&env);
+    assert_eq!(
+        client.try_initialize(&attacker, &token_client.address, &0, &0),
+        Err(Ok(GovernanceError::AlreadyInitialized))
+    );
+    assert_eq!(client.get_fees().transaction_fee, 50);
+}
+
 #[test]
 fn test_collect_transaction_fee() {
     let env = Env::default();
@@ -51,10 +75,10 @@ fn test_collect_transaction_fee() {
     client.initialize(&admin, &token_client.address, &50, &10);
     
     // Mint tokens para o usuário
-    token_client.mint(&user, &100_000_0000000);
+    mint(&env, &token_client.address, &user, 100_000_0000000);
     
     // Coletar taxa de 0.5% sobre 100k = 500
-    let fee = client.collect_transaction_fee(&user, &100_000_0000000);
+    let fee = client.collect_transaction_fee(&user, &token_client.address, &100_000_0000000);
     
     assert_eq!(fee, 500_0000000);
     
@@ -78,15 +102,15 @@ fn test_collect_gas_fee() {
     client.initialize(&admin, &token_client.address, &50, &10);
     
     // Mint tokens para o usuário
-    token_client.mint(&user, &100_000_0000000);
+    mint(&env, &token_client.address, &user, 100_000_0000000);
     
     // Coletar taxa de gás de 0.1% sobre 100k = 100
-    let gas_fee = client.collect_gas_fee(&user, &100_000_0000000);
+    let gas_fee = client.collect_gas_fee(&user, &token_client.address, &100_000_0000000);
     
     assert_eq!(gas_fee, 100_0000000);
     
     // Verificar que foi adicionado ao fundo de proteção
-    let fund = client.get_protection_fund();
+    let fund = client.get_protection_fund(&token_client.address);
     assert_eq!(fund.total_balance, 100_0000000);
 }
 
@@ -126,72 +150,131 @@ fn test_add_to_protection_fund() {
     client.initialize(&admin, &token_client.address, &50, &10);
     
     // Mint tokens para o contribuidor
-    token_client.mint(&contributor, &50_000_0000000);
+    mint(&env, &token_client.address, &contributor, 50_000_0000000);
     
     // Adicionar ao fundo
-    client.add_to_protection_fund(&contributor, &10_000_0000000);
+    client.add_to_protection_fund(&contributor, &token_client.address, &10_000_0000000);
     
-    let fund = client.get_protection_fund();
+    let fund = client.get_protection_fund(&token_client.address);
     assert_eq!(fund.total_balance, 10_000_0000000);
 }
 
 #[test]
-fn test_claim_protection() {
+fn test_open_and_close_claim() {
     let env = Env::default();
     env.mock_all_auths();
     
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let lender = Address::generate(&env);
-    let user = Address::generate(&env);
+    let loan_contract = Address::generate(&env);
     
     let (_, client) = create_governance_contract(&env);
     
     client.initialize(&admin, &token_client.address, &50, &10);
     
-    // Adicionar fundos ao fundo de proteção
-    token_client.mint(&user, &100_000_0000000);
-    client.add_to_protection_fund(&user, &50_000_0000000);
+    // Sem contrato de empréstimo configurado ninguém pode abrir claims
+    assert_eq!(
+        client.try_open_claim(&1, &token_client.address, &10_000_0000000),
+        Err(Ok(GovernanceError::LoanContractNotSet))
+    );
     
-    // Fazer claim de 10k (80% = 8k será pago)
-    let result = client.claim_protection(&lender, &1, &10_000_0000000);
+    client.set_loan_contract(&loan_contract);
+    client.open_claim(&1, &token_client.address, &10_000_0000000);
     
-    assert!(result);
+    // 80% do principal em aberto
+    let claim = client.get_claim(&1).unwrap();
+    assert_eq!(claim.status, ClaimStatus::Open);
+    assert_eq!(claim.payout, 8_000_0000000);
     
-    // Verificar que recebeu 80%
-    let lender_balance = token_client.balance(&lender);
-    assert_eq!(lender_balance, 8_000_0000000);
+    // Apenas um claim por empréstimo
+    assert_eq!(
+        client.try_open_claim(&1, &token_client.address, &10_000_0000000),
+        Err(Ok(GovernanceError::ClaimAlreadyExists))
+    );
     
-    // Verificar que o fundo diminuiu
-    let fund = client.get_protection_fund();
-    assert_eq!(fund.total_balance, 42_000_0000000); // 50k - 8k
+    let fund = client.get_protection_fund(&token_client.address);
     assert_eq!(fund.total_claims, 1);
+    assert_eq!(fund.active_claims, 1);
+    
+    client.close_claim(&1);
+    assert_eq!(client.get_claim(&1).unwrap().status, ClaimStatus::Closed);
+    assert_eq!(client.get_protection_fund(&token_client.address).active_claims, 0);
+    assert_eq!(client.try_pay_claim(&1), Err(Ok(GovernanceError::ClaimNotOpen)));
 }
 
 #[test]
-fn test_claim_protection_insufficient_funds() {
+fn test_reduce_claim_by_recovered_amount() {
     let env = Env::default();
     env.mock_all_auths();
     
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let lender = Address::generate(&env);
-    let user = Address::generate(&env);
+    let loan_contract = Address::generate(&env);
     
     let (_, client) = create_governance_contract(&env);
     
     client.initialize(&admin, &token_client.address, &50, &10);
+    client.set_loan_contract(&loan_contract);
+    client.open_claim(&1, &token_client.address, &10_000_0000000);
+    
+    // Recuperações fora do fundo reduzem o principal e a cobertura devida
+    assert_eq!(client.try_reduce_claim(&1, &0), Err(Ok(GovernanceError::InvalidClaimAmount)));
+    client.reduce_claim(&1, &4_000_0000000);
+    let claim = client.get_claim(&1).unwrap();
+    assert_eq!(claim.principal, 6_000_0000000);
+    assert_eq!(claim.payout, 4_800_0000000);
+    assert_eq!(claim.status, ClaimStatus::Open);
+    
+    // Recuperado todo o principal, o claim é encerrado sem pagamento
+    client.reduce_claim(&1, &7_000_0000000);
+    let claim = client.get_claim(&1).unwrap();
+    assert_eq!(claim.principal, 0);
+    assert_eq!(claim.payout, 0);
+    assert_eq!(claim.status, ClaimStatus::Closed);
+    assert_eq!(client.get_protection_fund(&token_client.address).active_claims, 0);
+    assert_eq!(client.try_reduce_claim(&1, &1), Err(Ok(GovernanceError::ClaimNotOpen)));
+    assert_eq!(client.try_reduce_claim(&2, &1), Err(Ok(GovernanceError::ClaimNotFound)));
+}
+
+#[test]
+fn test_migrate_legacy_claims() {
+    let env = Env::default();
+    env.mock_all_auths();
     
-    // Adicionar fundos pequenos ao fundo de proteção
-    token_client.mint(&user, &10_000_0000000);
-    client.add_to_protection_fund(&user, &5_000_0000000);
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
     
-    // Tentar claim maior que o disponível
-    let result = client.claim_protection(&lender, &1, &100_000_0000000);
+    let (contract_id, client) = create_governance_contract(&env);
     
-    assert!(!result); // Deve falhar
+    client.initialize(&admin, &token_client.address, &50, &10);
+    
+    // Layout 1: o claim guardava só o valor pago e ficava ativo para sempre
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&("CLAIM", 7u64), &8_000_0000000i128);
+        env.storage().instance().remove(&"SCHEMA_VERSION");
+        env.storage().instance().remove(&("PROTECTION_FUND", token_client.address.clone()));
+        env.storage().instance().set(&"PROTECTION_FUND", &ProtectionFund {
+            total_balance: 0,
+            total_claims: 1,
+            active_claims: 1,
+        });
+    });
+    assert_eq!(client.get_schema_version(), 1);
+    
+    assert_eq!(client.migrate(&vec![&env, 7u64, 8u64]), 3);
+    
+    let claim = client.get_claim(&7).unwrap();
+    assert_eq!(claim.status, ClaimStatus::Paid);
+    assert_eq!(claim.asset, token_client.address);
+    assert_eq!(claim.payout, 8_000_0000000);
+    assert_eq!(claim.principal, 10_000_0000000);
+    assert!(client.get_claim(&8).is_none());
+    assert_eq!(client.get_protection_fund(&token_client.address).active_claims, 0);
+    assert_eq!(client.get_protection_fund(&token_client.address).total_claims, 1);
+    assert_eq!(client.get_schema_version(), 3);
 }
 
 #[test]
@@ -210,18 +293,17 @@ fn test_withdraw_fees() {
     client.initialize(&admin, &token_client.address, &50, &10);
     
     // Coletar algumas taxas
-    token_client.mint(&user, &100_000_0000000);
-    client.collect_transaction_fee(&user, &100_000_0000000);
+    mint(&env, &token_client.address, &user, 100_000_0000000);
+    client.collect_transaction_fee(&user, &token_client.address, &100_000_0000000);
     
     // Sacar taxas
-    client.withdraw_fees(&recipient, &200_0000000);
+    client.withdraw_fees(&recipient, &token_client.address, &200_0000000);
     
     let recipient_balance = token_client.balance(&recipient);
     assert_eq!(recipient_balance, 200_0000000);
 }
 
 #[test]
-#[should_panic(expected = "Insufficient available funds")]
 fn test_withdraw_fees_from_protection_fund() {
     let env = Env::default();
     env.mock_all_auths();
@@ -237,11 +319,55 @@ fn test_withdraw_fees_from_protection_fund() {
     client.initialize(&admin, &token_client.address, &50, &10);
     
     // Adicionar ao fundo de proteção
-    token_client.mint(&user, &100_000_0000000);
-    client.add_to_protection_fund(&user, &50_000_0000000);
+    mint(&env, &token_client.address, &user, 100_000_0000000);
+    client.add_to_protection_fund(&user, &token_client.address, &50_000_0000000);
     
     // Tentar sacar mais do que está disponível (excluindo fundo de proteção)
-    client.withdraw_fees(&recipient, &60_000_0000000);
+    assert_eq!(
+        client.try_withdraw_fees(&recipient, &token_client.address, &60_000_0000000),
+        Err(Ok(GovernanceError::InsufficientAvailableFunds))
+    );
+}
+
+#[test]
+fn test_protection_funds_are_kept_per_asset() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let usdc = create_token_contract(&env, &token_admin);
+    let user = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    
+    let (_, client) = create_governance_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &50, &10);
+    
+    mint(&env, &token_client.address, &user, 100_000_0000000);
+    mint(&env, &usdc.address, &user, 100_000_0000000);
+    client.collect_gas_fee(&user, &token_client.address, &100_000_0000000);
+    client.add_to_protection_fund(&user, &usdc.address, &5_000_0000000);
+    client.collect_transaction_fee(&user, &usdc.address, &100_000_0000000);
+    
+    assert_eq!(client.get_protection_fund(&token_client.address).total_balance, 100_0000000);
+    assert_eq!(client.get_protection_fund(&usdc.address).total_balance, 5_000_0000000);
+    
+    // Só as taxas de transação em USDC estão disponíveis para saque nesse ativo
+    assert_eq!(
+        client.try_withdraw_fees(&recipient, &usdc.address, &501_0000000),
+        Err(Ok(GovernanceError::InsufficientAvailableFunds))
+    );
+    client.withdraw_fees(&recipient, &usdc.address, &500_0000000);
+    assert_eq!(usdc.balance(&recipient), 500_0000000);
+    
+    // Claims usam o fundo do ativo do empréstimo
+    client.set_loan_contract(&Address::generate(&env));
+    client.open_claim(&1, &usdc.address, &1_000_0000000);
+    assert_eq!(client.get_claim(&1).unwrap().asset, usdc.address);
+    assert_eq!(client.get_protection_fund(&usdc.address).active_claims, 1);
+    assert_eq!(client.get_protection_fund(&token_client.address).active_claims, 0);
 }
 
 #[test]
@@ -266,4 +392,71 @@ fn test_transfer_admin() {
     
     let fees = client.get_fees();
     assert_eq!(fees.transaction_fee, 75);
-}
\ No newline at end of file
+}
+#[test]
+fn test_set_pause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    
+    let (_, client) = create_governance_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &50, &10);
+    assert_eq!(client.get_pause(), PauseScope::None);
+    
+    client.set_pause(&PauseScope::Originations);
+    assert_eq!(client.get_pause(), PauseScope::Originations);
+    
+    client.set_pause(&PauseScope::None);
+    assert_eq!(client.get_pause(), PauseScope::None);
+    
+    // Só pausas totais entram no histórico; a pausa em curso termina na consulta
+    assert_eq!(client.get_pause_periods().len(), 0);
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    client.set_pause(&PauseScope::All);
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    assert_eq!(client.get_pause_periods(), vec![&env, PausePeriod { start: 1_000, end: 1_500 }]);
+    client.set_pause(&PauseScope::All);
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    client.set_pause(&PauseScope::Originations);
+    env.ledger().with_mut(|li| li.timestamp = 3_000);
+    assert_eq!(client.get_pause_periods(), vec![&env, PausePeriod { start: 1_000, end: 2_000 }]);
+}
+
+#[test]
+fn test_pause_periods_are_bounded() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    
+    let (_, client) = create_governance_contract(&env);
+    client.initialize(&admin, &token_client.address, &50, &10);
+    
+    // Pausa retomada no mesmo instante em que terminou é unida à anterior
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.set_pause(&PauseScope::All);
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    client.set_pause(&PauseScope::None);
+    client.set_pause(&PauseScope::All);
+    env.ledger().with_mut(|li| li.timestamp = 300);
+    client.set_pause(&PauseScope::None);
+    assert_eq!(client.get_pause_periods(), vec![&env, PausePeriod { start: 100, end: 300 }]);
+    
+    // Acima do limite, as pausas mais antigas saem do histórico
+    for i in 1..=40u64 {
+        env.ledger().with_mut(|li| li.timestamp = i * 1_000);
+        client.set_pause(&PauseScope::All);
+        env.ledger().with_mut(|li| li.timestamp = i * 1_000 + 10);
+        client.set_pause(&PauseScope::None);
+    }
+    let periods = client.get_pause_periods();
+    assert_eq!(periods.len(), 32);
+    assert_eq!(periods.get(0).unwrap(), PausePeriod { start: 9_000, end: 9_010 });
+    assert_eq!(periods.last().unwrap(), PausePeriod { start: 40_000, end: 40_010 });
+}
@@ -1,18 +1,129 @@
 #![cfg(test)]
 
-use soroban_sdk::{testutils::Address as _, token, Address, Env, String, Vec};
-use mithril_contracts::loan::{LoanContract, LoanContractClient, LoanStatus};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{testutils::{storage::Persistent as _, Address as _, Events as _, Ledger}, token, vec, TryFromVal, xdr::ToXdr, Address, BytesN, Env, Map, String, Symbol, Val, Vec};
+use mithril_contracts::loan::{AmortizationMethod, CollateralTerms, LoanContract, LoanContractClient, LoanStatus, LoanError, ApplicationStatus, ProposalStatus, LoanV1, PaymentV1, InvestmentCardV1, RequestCardV1, LoanApplicationV1, CardType, PixAttestation, PixStatus, PixType};
+use mithril_contracts::governance::{ClaimStatus, GovernanceContract, GovernanceContractClient, GovernanceError, PauseScope};
+use mithril_contracts::credit_score::{CreditScoreContract, CreditScoreContractClient, CreditScoreError, OffChainData};
 
 fn create_token_contract<'a>(env: &Env, admin: &Address) -> token::Client<'a> {
     token::Client::new(env, &env.register_stellar_asset_contract(admin.clone()))
 }
 
+fn mint(env: &Env, token: &Address, to: &Address, amount: i128) {
+    token::StellarAssetClient::new(env, token).mint(to, &amount);
+}
+
+fn create_governance_contract(env: &Env, admin: &Address, token: &Address) -> Address {
+    let contract_id = env.register_contract(None, GovernanceContract);
+    GovernanceContractClient::new(env, &contract_id).initialize(admin, token, &50, &10);
+    contract_id
+}
+
+fn create_credit_score_contract(env: &Env, admin: &Address) -> Address {
+    let contract_id = env.register_contract(None, CreditScoreContract);
+    CreditScoreContractClient::new(env, &contract_id).initialize(admin);
+    contract_id
+}
+
+// Score 40 (risco médio) com todos os dados off-chain e bureau máximo
+fn give_credit_score(env: &Env, credit_score: &Address, user: &Address) {
+    let off_chain_data = OffChainData {
+        bank_statements: true,
+        pix_history: true,
+        invoices: true,
+        credit_bureau: true,
+    };
+    CreditScoreContractClient::new(env, credit_score).update_credit_score(user, &off_chain_data, &1000);
+}
+
 fn create_loan_contract<'a>(env: &Env) -> (Address, LoanContractClient<'a>) {
     let contract_id = env.register_contract(None, LoanContract);
     let client = LoanContractClient::new(env, &contract_id);
     (contract_id, client)
 }
 
+// Confirma o PIX de desembolso, ativando o empréstimo criado em `Pending`
+fn disburse(env: &Env, client: &LoanContractClient, loan_id: u64) {
+    let payment_id = String::from_str(env, &format!("payout-{}", loan_id));
+    let amount = client.get_loan(&loan_id).unwrap().amount;
+    client.register_pix_payment(
+        &payment_id,
+        &String::from_str(env, &format!("pedido-payout-{}", loan_id)),
+        &amount,
+        &PixType::CreditPayout,
+        &loan_id,
+    );
+    client.update_pix_payment_status(&payment_id, &PixStatus::Completed);
+}
+
+struct LoanSetup<'a> {
+    client: LoanContractClient<'a>,
+    token: token::Client<'a>,
+    governance: Address,
+    credit_score: Address,
+    investor: Address,
+    borrower: Address,
+    loan_id: u64,
+}
+
+// Empréstimo ativo sem juros (Price) financiado por um card de investimento
+fn setup_active_loan<'a>(env: &Env, amount: i128, installments: u32) -> LoanSetup<'a> {
+    setup_loan_with_terms(env, amount, 0, installments, AmortizationMethod::Price, Vec::new(env))
+}
+
+fn setup_loan_with_terms<'a>(
+    env: &Env,
+    amount: i128,
+    interest_rate: u32,
+    installments: u32,
+    amortization: AmortizationMethod,
+    payment_dates: Vec<u64>,
+) -> LoanSetup<'a> {
+    let admin = Address::generate(env);
+    let token_admin = Address::generate(env);
+    let token_client = create_token_contract(env, &token_admin);
+    let governance = create_governance_contract(env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(env, &admin);
+    let investor = Address::generate(env);
+    let borrower = Address::generate(env);
+    
+    let (contract_id, client) = create_loan_contract(env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    CreditScoreContractClient::new(env, &credit_score).set_loan_contract(&contract_id);
+    GovernanceContractClient::new(env, &governance).set_loan_contract(&contract_id);
+    give_credit_score(env, &credit_score, &borrower);
+    
+    mint(env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &1_000_0000000,
+        &interest_rate,
+        &installments,
+        &40,
+        &amortization,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &amount);
+    let loan_id = client.approve_application(&app_id, &installments, &payment_dates);
+    disburse(env, &client, loan_id);
+    
+    LoanSetup {
+        client,
+        token: token_client,
+        governance,
+        credit_score,
+        investor,
+        borrower,
+        loan_id,
+    }
+}
+
 #[test]
 fn test_initialize() {
     let env = Env::default();
@@ -21,8 +132,8 @@ fn test_initialize() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     
     let (_, client) = create_loan_contract(&env);
     
@@ -34,6 +145,28 @@ fn test_initialize() {
     );
 }
 
+#[test]
+fn test_initialize_twice_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    
+    let (_, client) = create_loan_contract(&env);
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    
+    // Um novo endereço não pode se declarar admin de um contrato já inicializado
+    let attacker = Address::generate(&env);
+    assert_eq!(
+        client.try_initialize(&attacker, &token_client.address, &governance, &credit_score),
+        Err(Ok(LoanError::AlreadyInitialized))
+    );
+}
+
 #[test]
 fn test_create_investment_card() {
     let env = Env::default();
@@ -42,21 +175,26 @@ fn test_create_investment_card() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let investor = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
     
     client.initialize(&admin, &token_client.address, &governance, &credit_score);
     
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
     let card_id = client.create_investment_card(
         &investor,
+        &token_client.address,
         &100_000_0000000,  // 100k max
         &5_000_0000000,    // 5k min
         &500,              // 5% interest
         &12,               // 12 installments
-        &50,               // min score 50
+        &40,               // min score 40
+        &AmortizationMethod::Price,
+        &None,
     );
     
     assert_eq!(card_id, 1);
@@ -78,8 +216,8 @@ fn test_create_request_card() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let borrower = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
@@ -93,10 +231,15 @@ fn test_create_request_card() {
     
     let card_id = client.create_request_card(
         &borrower,
+        &token_client.address,
         &20_000_0000000,
         &6,
         &payment_dates,
+        &AmortizationMethod::Price,
+        &500,
+        &(env.ledger().timestamp() + 7 * 86400),
         &description,
+        &None,
     );
     
     assert_eq!(card_id, 1);
@@ -119,23 +262,29 @@ fn test_apply_to_investment_card() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let investor = Address::generate(&env);
     let borrower = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
     
     client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
     
     // Criar card de investimento
     let card_id = client.create_investment_card(
         &investor,
+        &token_client.address,
         &100_000_0000000,
         &5_000_0000000,
         &500,
         &12,
-        &50,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
     );
     
     // Tomador aplica para o card
@@ -149,7 +298,6 @@ fn test_apply_to_investment_card() {
 }
 
 #[test]
-#[should_panic(expected = "Card not found")]
 fn test_apply_to_nonexistent_card() {
     let env = Env::default();
     env.mock_all_auths();
@@ -157,8 +305,8 @@ fn test_apply_to_nonexistent_card() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let borrower = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
@@ -166,11 +314,13 @@ fn test_apply_to_nonexistent_card() {
     client.initialize(&admin, &token_client.address, &governance, &credit_score);
     
     // Tentar aplicar para card inexistente
-    client.apply_to_investment_card(&borrower, &999, &50_000_0000000);
+    assert_eq!(
+        client.try_apply_to_investment_card(&borrower, &999, &50_000_0000000),
+        Err(Ok(LoanError::CardNotFound))
+    );
 }
 
 #[test]
-#[should_panic(expected = "Amount out of range")]
 fn test_apply_with_amount_out_of_range() {
     let env = Env::default();
     env.mock_all_auths();
@@ -178,26 +328,35 @@ fn test_apply_with_amount_out_of_range() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let investor = Address::generate(&env);
     let borrower = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
     
     client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
     
     let card_id = client.create_investment_card(
         &investor,
+        &token_client.address,
         &100_000_0000000,
         &5_000_0000000,
         &500,
         &12,
-        &50,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
     );
     
     // Tentar aplicar com valor acima do máximo
-    client.apply_to_investment_card(&borrower, &card_id, &150_000_0000000);
+    assert_eq!(
+        client.try_apply_to_investment_card(&borrower, &card_id, &150_000_0000000),
+        Err(Ok(LoanError::AmountOutOfRange))
+    );
 }
 
 #[test]
@@ -208,21 +367,26 @@ fn test_cancel_investment_card() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let investor = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
     
     client.initialize(&admin, &token_client.address, &governance, &credit_score);
     
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
     let card_id = client.create_investment_card(
         &investor,
+        &token_client.address,
         &100_000_0000000,
         &5_000_0000000,
         &500,
         &12,
-        &50,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
     );
     
     // Cancelar card
@@ -240,8 +404,8 @@ fn test_cancel_request_card() {
     let admin = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let token_client = create_token_contract(&env, &token_admin);
-    let governance = Address::generate(&env);
-    let credit_score = Address::generate(&env);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
     let borrower = Address::generate(&env);
     
     let (_, client) = create_loan_contract(&env);
@@ -255,10 +419,15 @@ fn test_cancel_request_card() {
     
     let card_id = client.create_request_card(
         &borrower,
+        &token_client.address,
         &20_000_0000000,
         &6,
         &payment_dates,
+        &AmortizationMethod::Price,
+        &500,
+        &(env.ledger().timestamp() + 7 * 86400),
         &description,
+        &None,
     );
     
     // Cancelar card
@@ -266,4 +435,2446 @@ fn test_cancel_request_card() {
     
     let card = client.get_request_card(&card_id).unwrap();
     assert!(!card.is_active);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_investment_card_escrows_capital() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    
+    let (contract_id, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    // O capital sai da carteira do investidor e fica em custódia no contrato
+    assert_eq!(token_client.balance(&investor), 0);
+    assert_eq!(token_client.balance(&contract_id), 100_000_0000000);
+    
+    let card = client.get_investment_card(&card_id).unwrap();
+    assert_eq!(card.escrowed_amount, 100_000_0000000);
+}
+
+#[test]
+fn test_approve_application_draws_from_escrow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (contract_id, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &50_000_0000000);
+    
+    // A carteira do investidor está vazia, mas a aprovação usa a custódia
+    let loan_id = client.approve_application(&app_id, &12, &Vec::new(&env));
+    
+    // O principal fica em custódia até o PIX de desembolso ser confirmado
+    assert_eq!(client.get_loan(&loan_id).unwrap().status, LoanStatus::Pending);
+    assert_eq!(token_client.balance(&borrower), 0);
+    disburse(&env, &client, loan_id);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.lender, investor);
+    assert_eq!(loan.status, LoanStatus::Active);
+    
+    // 50k para o tomador; 0,5% de taxa de transação e 0,1% para o fundo de proteção
+    assert_eq!(token_client.balance(&borrower), 50_000_0000000);
+    assert_eq!(token_client.balance(&governance), 300_0000000);
+    assert_eq!(token_client.balance(&contract_id), 49_700_0000000);
+    let fund = GovernanceContractClient::new(&env, &governance).get_protection_fund(&token_client.address);
+    assert_eq!(fund.total_balance, 50_0000000);
+    
+    let card = client.get_investment_card(&card_id).unwrap();
+    assert_eq!(card.escrowed_amount, 49_700_0000000);
+    assert_eq!(card.total_invested, 50_000_0000000);
+}
+
+#[test]
+fn test_apply_above_escrowed_funds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (_, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &50_000_0000000);
+    client.approve_application(&app_id, &12, &Vec::new(&env));
+    
+    // Restam menos de 50k em custódia após o primeiro empréstimo e a taxa
+    assert_eq!(
+        client.try_apply_to_investment_card(&borrower, &card_id, &50_000_0000000),
+        Err(Ok(LoanError::InsufficientEscrow))
+    );
+}
+
+#[test]
+fn test_cancel_investment_card_refunds_escrow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (contract_id, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &10_000_0000000);
+    let loan_id = client.approve_application(&app_id, &12, &Vec::new(&env));
+    disburse(&env, &client, loan_id);
+    
+    client.cancel_card(&card_id, &true);
+    
+    // 10k emprestados + 60 de taxas; o restante volta ao investidor
+    assert_eq!(token_client.balance(&investor), 89_940_0000000);
+    assert_eq!(token_client.balance(&contract_id), 0);
+    
+    let card = client.get_investment_card(&card_id).unwrap();
+    assert!(!card.is_active);
+    assert_eq!(card.escrowed_amount, 0);
+}
+
+#[test]
+fn test_apply_below_card_target_risk_level() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (_, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    // Card exige score mínimo 60; o tomador tem 40
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &60,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    assert_eq!(
+        client.try_apply_to_investment_card(&borrower, &card_id, &10_000_0000000),
+        Err(Ok(LoanError::CreditScoreTooLow))
+    );
+}
+
+#[test]
+fn test_apply_above_borrower_credit_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (_, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    // Card sem score mínimo, mas tomador sem score só pode pegar até 5k
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &1_000_0000000,
+        &500,
+        &12,
+        &0,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    assert_eq!(
+        client.try_apply_to_investment_card(&borrower, &card_id, &10_000_0000000),
+        Err(Ok(LoanError::CreditLimitExceeded))
+    );
+}
+
+#[test]
+fn test_fund_request_card_above_borrower_credit_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let borrower = Address::generate(&env);
+    let lender = Address::generate(&env);
+    
+    let (_, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    
+    let card_id = client.create_request_card(
+        &borrower,
+        &token_client.address,
+        &20_000_0000000,
+        &6,
+        &Vec::new(&env),
+        &AmortizationMethod::Price,
+        &500,
+        &(env.ledger().timestamp() + 7 * 86400),
+        &String::from_str(&env, "Need funds"),
+        &None,
+    );
+    
+    mint(&env, &token_client.address, &lender, 30_000_0000000);
+    assert_eq!(
+        client.try_fund_request_card(&lender, &card_id, &20_000_0000000),
+        Err(Ok(LoanError::CreditLimitExceeded))
+    );
+}
+
+#[test]
+fn test_make_payment_records_credit_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (contract_id, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    let score_client = CreditScoreContractClient::new(&env, &credit_score);
+    score_client.set_loan_contract(&contract_id);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &12_000_0000000);
+    let loan_id = client.approve_application(&app_id, &12, &Vec::new(&env));
+    disburse(&env, &client, loan_id);
+    
+    let installment = client.get_loan(&loan_id).unwrap().installment_amount;
+    client.make_payment(&loan_id, &installment);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    let history = score_client.get_payment_history(&borrower).unwrap();
+    assert_eq!(history.len(), 1);
+    
+    let record = history.get(0).unwrap();
+    assert_eq!(record.loan_id, loan_id);
+    assert_eq!(record.amount, loan.installment_amount);
+    assert!(record.on_time);
+    
+    let score = score_client.get_score(&borrower).unwrap();
+    assert_eq!(score.total_transactions, 1);
+}
+
+#[test]
+fn test_mark_as_defaulted_records_default() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let token_client = create_token_contract(&env, &token_admin);
+    let governance = create_governance_contract(&env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(&env, &admin);
+    let investor = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    
+    let (contract_id, client) = create_loan_contract(&env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    let score_client = CreditScoreContractClient::new(&env, &credit_score);
+    score_client.set_loan_contract(&contract_id);
+    let governance_client = GovernanceContractClient::new(&env, &governance);
+    governance_client.set_loan_contract(&contract_id);
+    give_credit_score(&env, &credit_score, &borrower);
+    
+    mint(&env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &5_000_0000000,
+        &500,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &12_000_0000000);
+    let loan_id = client.approve_application(&app_id, &12, &Vec::new(&env));
+    disburse(&env, &client, loan_id);
+    
+    // Primeira parcela vence em 30 dias; carência de 7 dias
+    env.ledger().with_mut(|li| li.timestamp += 38 * 86400);
+    client.mark_as_defaulted(&loan_id);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Defaulted);
+    
+    let defaults = score_client.get_default_history(&borrower).unwrap();
+    assert_eq!(defaults.len(), 1);
+    assert_eq!(defaults.get(0).unwrap().outstanding_amount, loan.total_due);
+    
+    let score = score_client.get_score(&borrower).unwrap();
+    assert_eq!(score.default_count, 1);
+    
+    // Nenhuma parcela paga: o claim cobre 80% dos 12k de principal
+    let claim = governance_client.get_claim(&loan_id).unwrap();
+    assert_eq!(claim.status, ClaimStatus::Open);
+    assert_eq!(claim.principal, 12_000_0000000);
+    assert_eq!(claim.payout, 9_600_0000000);
+    assert_eq!(governance_client.get_protection_fund(&token_client.address).active_claims, 1);
+}
+
+#[test]
+fn test_protection_claim_pays_lenders_pro_rata() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, governance, borrower, card_id) = setup_request_card(&env);
+    let governance_client = GovernanceContractClient::new(&env, &governance);
+    let lender_a = Address::generate(&env);
+    let lender_b = Address::generate(&env);
+    mint(&env, &token_client.address, &lender_a, 20_000_0000000);
+    mint(&env, &token_client.address, &lender_b, 20_000_0000000);
+    client.fund_request_card(&lender_a, &card_id, &15_000_0000000);
+    let loan_id = client.fund_request_card(&lender_b, &card_id, &5_000_0000000).unwrap();
+    disburse(&env, &client, loan_id);
+    
+    // Uma parcela de 2k paga; o principal em aberto é 18k
+    mint(&env, &token_client.address, &borrower, 2_000_0000000);
+    client.make_payment(&loan_id, &2_000_0000000);
+    env.ledger().with_mut(|li| li.timestamp += 68 * 86400);
+    client.mark_as_defaulted(&loan_id);
+    
+    let claim = governance_client.get_claim(&loan_id).unwrap();
+    assert_eq!(claim.principal, 18_000_0000000);
+    assert_eq!(claim.payout, 14_400_0000000);
+    
+    // O fundo ainda não cobre o claim, que continua aberto
+    assert_eq!(
+        governance_client.try_pay_claim(&loan_id),
+        Err(Ok(GovernanceError::InsufficientProtectionFund))
+    );
+    
+    let donor = Address::generate(&env);
+    mint(&env, &token_client.address, &donor, 20_000_0000000);
+    governance_client.add_to_protection_fund(&donor, &token_client.address, &20_000_0000000);
+    let balance_a = token_client.balance(&lender_a);
+    let balance_b = token_client.balance(&lender_b);
+    
+    assert_eq!(governance_client.pay_claim(&loan_id), 14_400_0000000);
+    assert_eq!(token_client.balance(&lender_a), balance_a + 10_800_0000000);
+    assert_eq!(token_client.balance(&lender_b), balance_b + 3_600_0000000);
+    
+    let fund = governance_client.get_protection_fund(&token_client.address);
+    assert_eq!(fund.active_claims, 0);
+    assert_eq!(fund.total_claims, 1);
+    assert_eq!(governance_client.get_claim(&loan_id).unwrap().status, ClaimStatus::Paid);
+    assert_eq!(governance_client.try_pay_claim(&loan_id), Err(Ok(GovernanceError::ClaimNotOpen)));
+}
+
+#[test]
+fn test_partial_payments_accumulate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    // 12k sem juros em 12 parcelas de 1.000
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.installment_amount, 1_000_0000000);
+    
+    client.make_payment(&setup.loan_id, &400_0000000);
+    client.make_payment(&setup.loan_id, &400_0000000);
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.paid_installments, 0);
+    assert_eq!(loan.current_installment_paid, 800_0000000);
+    assert_eq!(loan.total_paid, 800_0000000);
+    
+    // Completa a primeira parcela
+    client.make_payment(&setup.loan_id, &200_0000000);
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.paid_installments, 1);
+    assert_eq!(loan.current_installment_paid, 0);
+    assert_eq!(loan.total_paid, 1_000_0000000);
+    assert_eq!(setup.token.balance(&setup.investor), 1_000_0000000);
+    
+    let payments = client.get_payment_history(&setup.loan_id).unwrap();
+    assert_eq!(payments.len(), 3);
+    assert_eq!(payments.get(2).unwrap().installment_number, 1);
+    
+    // Só parcelas quitadas entram no histórico do credit score
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    assert_eq!(score_client.get_payment_history(&setup.borrower).unwrap().len(), 1);
+}
+
+#[test]
+fn test_payment_covering_several_installments() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    // Duas parcelas e meia de uma vez
+    client.make_payment(&setup.loan_id, &2_500_0000000);
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.paid_installments, 2);
+    assert_eq!(loan.current_installment_paid, 500_0000000);
+    assert_eq!(loan.total_paid, 2_500_0000000);
+    
+    let payments = client.get_payment_history(&setup.loan_id).unwrap();
+    assert_eq!(payments.len(), 3);
+    assert_eq!(payments.get(0).unwrap().installment_number, 1);
+    assert_eq!(payments.get(1).unwrap().installment_number, 2);
+    assert_eq!(payments.get(2).unwrap().installment_number, 3);
+    assert_eq!(payments.get(2).unwrap().amount, 500_0000000);
+    
+    assert_eq!(client.get_outstanding_balance(&setup.loan_id), 9_500_0000000);
+    
+    // A próxima data de vencimento passa a ser a da terceira parcela
+    let schedule = client.get_schedule(&setup.loan_id).unwrap();
+    assert_eq!(loan.next_payment_date, schedule.get(2).unwrap().due_date);
+}
+
+#[test]
+fn test_pay_off_loan() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    mint(&env, &setup.token.address, &setup.borrower, 1_000_0000000);
+    client.make_payment(&setup.loan_id, &300_0000000);
+    
+    let paid = client.pay_off_loan(&setup.loan_id);
+    assert_eq!(paid, 11_700_0000000);
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Completed);
+    assert_eq!(loan.paid_installments, 12);
+    assert_eq!(loan.total_paid, 12_000_0000000);
+    assert_eq!(client.get_outstanding_balance(&setup.loan_id), 0);
+    
+    let payments = client.get_payment_history(&setup.loan_id).unwrap();
+    let mut total = 0;
+    for payment in payments.iter() {
+        total += payment.amount;
+    }
+    assert_eq!(total, loan.total_paid);
+}
+
+#[test]
+fn test_payment_above_outstanding_balance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    
+    mint(&env, &setup.token.address, &setup.borrower, 1_000_0000000);
+    assert_eq!(
+        setup.client.try_make_payment(&setup.loan_id, &12_000_0000001),
+        Err(Ok(LoanError::AmountExceedsOutstanding))
+    );
+}
+
+#[test]
+fn test_price_schedule() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    // 10k a 2% a.m. em 3 parcelas fixas
+    let setup = setup_loan_with_terms(&env, 10_000_0000000, 200, 3, AmortizationMethod::Price, Vec::new(&env));
+    
+    let schedule = setup.client.get_schedule(&setup.loan_id).unwrap();
+    assert_eq!(schedule.len(), 3);
+    assert_eq!(schedule.get(0).unwrap().interest, 200_0000000);
+    assert_eq!(schedule.get(0).unwrap().amount, 3_467_5467259);
+    assert_eq!(schedule.get(1).unwrap().amount, 3_467_5467259);
+    // Diferença de arredondamento vai para a última parcela
+    assert_eq!(schedule.get(2).unwrap().amount, 3_467_5467260);
+    
+    let mut principal = 0;
+    for installment in schedule.iter() {
+        principal += installment.principal;
+    }
+    assert_eq!(principal, 10_000_0000000);
+    
+    let loan = setup.client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.installment_amount, 3_467_5467259);
+    assert_eq!(loan.total_due, 10_402_6401778);
+}
+
+#[test]
+fn test_sac_schedule() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_loan_with_terms(&env, 10_000_0000000, 200, 3, AmortizationMethod::Sac, Vec::new(&env));
+    
+    let schedule = setup.client.get_schedule(&setup.loan_id).unwrap();
+    assert_eq!(schedule.get(0).unwrap().principal, 3_333_3333333);
+    assert_eq!(schedule.get(0).unwrap().amount, 3_533_3333333);
+    assert_eq!(schedule.get(1).unwrap().amount, 3_466_6666666);
+    assert_eq!(schedule.get(2).unwrap().principal, 3_333_3333334);
+    assert_eq!(schedule.get(2).unwrap().amount, 3_400_0000001);
+}
+
+#[test]
+fn test_bullet_schedule_with_payment_dates() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let now = env.ledger().timestamp();
+    let mut payment_dates = Vec::new(&env);
+    payment_dates.push_back(now + 15 * 86400);
+    payment_dates.push_back(now + 45 * 86400);
+    
+    let setup = setup_loan_with_terms(&env, 10_000_0000000, 200, 3, AmortizationMethod::Bullet, payment_dates);
+    
+    let schedule = setup.client.get_schedule(&setup.loan_id).unwrap();
+    assert_eq!(schedule.get(0).unwrap().principal, 0);
+    assert_eq!(schedule.get(0).unwrap().amount, 200_0000000);
+    assert_eq!(schedule.get(1).unwrap().amount, 200_0000000);
+    assert_eq!(schedule.get(2).unwrap().amount, 10_200_0000000);
+    
+    // Datas informadas são usadas; as demais seguem o ciclo mensal
+    assert_eq!(schedule.get(0).unwrap().due_date, now + 15 * 86400);
+    assert_eq!(schedule.get(1).unwrap().due_date, now + 45 * 86400);
+    assert_eq!(schedule.get(2).unwrap().due_date, now + 90 * 86400);
+    
+    let loan = setup.client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.next_payment_date, now + 15 * 86400);
+}
+
+#[test]
+fn test_late_payment_charges_fine_and_interest() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    // Primeira parcela de 1.000 paga com 10 dias de atraso
+    let schedule = client.get_schedule(&setup.loan_id).unwrap();
+    let due_date = schedule.get(0).unwrap().due_date;
+    env.ledger().with_mut(|li| li.timestamp = due_date + 10 * 86400);
+    
+    // Multa de 2% (20) + juros de mora de 1% a.m. por 10 dias (3,3333333)
+    assert_eq!(client.get_late_charges(&setup.loan_id), 23_3333333);
+    assert_eq!(client.get_outstanding_balance(&setup.loan_id), 12_023_3333333);
+    
+    client.make_payment(&setup.loan_id, &1_023_3333333);
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.paid_installments, 1);
+    assert_eq!(loan.total_paid, 1_000_0000000);
+    assert_eq!(loan.late_charges_paid, 23_3333333);
+    assert_eq!(setup.token.balance(&setup.investor), 1_023_3333333);
+    
+    let payment = client.get_payment_history(&setup.loan_id).unwrap().get(0).unwrap();
+    assert_eq!(payment.amount, 1_000_0000000);
+    assert_eq!(payment.late_fee, 20_0000000);
+    assert_eq!(payment.late_interest, 3_3333333);
+    assert!(!payment.was_on_time);
+}
+
+#[test]
+fn test_partial_late_payment_splits_charges() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    let schedule = client.get_schedule(&setup.loan_id).unwrap();
+    let due_date = schedule.get(0).unwrap().due_date;
+    env.ledger().with_mut(|li| li.timestamp = due_date + 30 * 86400);
+    
+    // Com 30 dias de atraso os encargos são 3%: 515 abatem 500 da parcela
+    client.make_payment(&setup.loan_id, &515_0000000);
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.paid_installments, 0);
+    assert_eq!(loan.current_installment_paid, 500_0000000);
+    assert_eq!(loan.late_charges_paid, 15_0000000);
+    
+    let payment = client.get_payment_history(&setup.loan_id).unwrap().get(0).unwrap();
+    assert_eq!(payment.late_fee, 10_0000000);
+    assert_eq!(payment.late_interest, 5_0000000);
+    
+    // Encargos restantes incidem só sobre o saldo da parcela
+    assert_eq!(client.get_late_charges(&setup.loan_id), 15_0000000);
+}
+
+#[test]
+fn test_on_time_payment_has_no_late_charges() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    // Dentro do dia de tolerância não há encargos
+    let schedule = client.get_schedule(&setup.loan_id).unwrap();
+    env.ledger().with_mut(|li| li.timestamp = schedule.get(0).unwrap().due_date + 86400);
+    
+    assert_eq!(client.get_late_charges(&setup.loan_id), 0);
+    client.make_payment(&setup.loan_id, &1_000_0000000);
+    
+    let payment = client.get_payment_history(&setup.loan_id).unwrap().get(0).unwrap();
+    assert_eq!(payment.late_fee, 0);
+    assert_eq!(payment.late_interest, 0);
+    assert!(payment.was_on_time);
+}
+
+#[test]
+fn test_set_late_fee_config_above_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    
+    setup.client.set_late_fee_config(&100, &50);
+    assert_eq!(setup.client.get_late_fee_config().fine_bps, 100);
+    
+    // Multa de 10% excede o limite de 2% do CDC
+    assert_eq!(
+        setup.client.try_set_late_fee_config(&1000, &100),
+        Err(Ok(LoanError::LateFeeAboveCap))
+    );
+}
+
+// Card de solicitação de 20k em 10 parcelas sem juros, aberto por 7 dias
+fn setup_request_card<'a>(env: &Env) -> (LoanContractClient<'a>, token::Client<'a>, Address, Address, u64) {
+    let admin = Address::generate(env);
+    let token_admin = Address::generate(env);
+    let token_client = create_token_contract(env, &token_admin);
+    let governance = create_governance_contract(env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(env, &admin);
+    let borrower = Address::generate(env);
+    
+    let (contract_id, client) = create_loan_contract(env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    CreditScoreContractClient::new(env, &credit_score).set_loan_contract(&contract_id);
+    GovernanceContractClient::new(env, &governance).set_loan_contract(&contract_id);
+    give_credit_score(env, &credit_score, &borrower);
+    
+    let card_id = client.create_request_card(
+        &borrower,
+        &token_client.address,
+        &20_000_0000000,
+        &10,
+        &Vec::new(env),
+        &AmortizationMethod::Price,
+        &0,
+        &(env.ledger().timestamp() + 7 * 86400),
+        &String::from_str(env, "Equipamento novo"),
+        &None,
+    );
+    
+    (client, token_client, governance, borrower, card_id)
+}
+
+#[test]
+fn test_crowdfunded_request_card() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, governance, borrower, card_id) = setup_request_card(&env);
+    let lender_a = Address::generate(&env);
+    let lender_b = Address::generate(&env);
+    mint(&env, &token_client.address, &lender_a, 20_000_0000000);
+    mint(&env, &token_client.address, &lender_b, 20_000_0000000);
+    
+    // Primeiro aporte não completa a captação
+    assert_eq!(client.fund_request_card(&lender_a, &card_id, &12_000_0000000), None);
+    
+    let card = client.get_request_card(&card_id).unwrap();
+    assert_eq!(card.funded_amount, 12_000_0000000);
+    assert!(!card.is_funded);
+    
+    // Aporte + 0,6% de taxas ficam em custódia
+    assert_eq!(token_client.balance(&lender_a), 7_928_0000000);
+    
+    let loan_id = client.fund_request_card(&lender_b, &card_id, &8_000_0000000).unwrap();
+    disburse(&env, &client, loan_id);
+    
+    let card = client.get_request_card(&card_id).unwrap();
+    assert!(card.is_funded);
+    assert_eq!(token_client.balance(&borrower), 20_000_0000000);
+    assert_eq!(token_client.balance(&governance), 120_0000000);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.lender, lender_a);
+    assert_eq!(loan.amount, 20_000_0000000);
+    
+    let shares = client.get_loan_shares(&loan_id).unwrap();
+    assert_eq!(shares.len(), 2);
+    
+    // Parcela de 2.000 dividida 60/40 entre os credores
+    client.make_payment(&loan_id, &2_000_0000000);
+    assert_eq!(token_client.balance(&lender_a), 7_928_0000000 + 1_200_0000000);
+    assert_eq!(token_client.balance(&lender_b), 11_952_0000000 + 800_0000000);
+}
+
+#[test]
+fn test_request_card_fees_follow_governance_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, governance, _, card_id) = setup_request_card(&env);
+    let governance_client = GovernanceContractClient::new(&env, &governance);
+    let lender_a = Address::generate(&env);
+    let lender_b = Address::generate(&env);
+    mint(&env, &token_client.address, &lender_a, 20_000_0000000);
+    mint(&env, &token_client.address, &lender_b, 20_000_0000000);
+    
+    client.fund_request_card(&lender_a, &card_id, &10_000_0000000);
+    
+    // Aumento das taxas durante a captação: o credor A reservou apenas 0,6%
+    governance_client.update_fees(&100, &20);
+    assert_eq!(
+        client.try_fund_request_card(&lender_b, &card_id, &10_000_0000000),
+        Err(Ok(LoanError::FeeConfigChanged))
+    );
+    
+    // Com taxas menores a captação é concluída; as taxas ficam reservadas até o desembolso
+    governance_client.update_fees(&30, &10);
+    let loan_id = client.fund_request_card(&lender_b, &card_id, &10_000_0000000).unwrap();
+    assert_eq!(token_client.balance(&lender_a), 9_940_0000000);
+    assert_eq!(token_client.balance(&governance), 0);
+    
+    // O desembolso cobra o valor vigente e devolve a sobra reservada
+    disburse(&env, &client, loan_id);
+    assert_eq!(token_client.balance(&lender_a), 9_960_0000000);
+    assert_eq!(token_client.balance(&lender_b), 9_960_0000000);
+    assert_eq!(token_client.balance(&governance), 80_0000000);
+    assert_eq!(governance_client.get_protection_fund(&token_client.address).total_balance, 20_0000000);
+}
+
+#[test]
+fn test_refund_request_card_after_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, _, card_id) = setup_request_card(&env);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 20_000_0000000);
+    
+    client.fund_request_card(&lender, &card_id, &5_000_0000000);
+    client.fund_request_card(&lender, &card_id, &5_000_0000000);
+    
+    let commitments = client.get_funding_commitments(&card_id).unwrap();
+    assert_eq!(commitments.len(), 1);
+    assert_eq!(commitments.get(0).unwrap().amount, 10_000_0000000);
+    
+    env.ledger().with_mut(|li| li.timestamp += 8 * 86400);
+    client.refund_request_card(&card_id);
+    
+    assert_eq!(token_client.balance(&lender), 20_000_0000000);
+    
+    let card = client.get_request_card(&card_id).unwrap();
+    assert!(!card.is_active);
+    assert_eq!(card.funded_amount, 0);
+}
+
+#[test]
+fn test_refund_request_card_before_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, _, card_id) = setup_request_card(&env);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 20_000_0000000);
+    
+    client.fund_request_card(&lender, &card_id, &5_000_0000000);
+    assert_eq!(
+        client.try_refund_request_card(&card_id),
+        Err(Ok(LoanError::FundingDeadlineNotReached))
+    );
+}
+
+#[test]
+fn test_fund_request_card_after_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, _, card_id) = setup_request_card(&env);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 20_000_0000000);
+    
+    env.ledger().with_mut(|li| li.timestamp += 8 * 86400);
+    assert_eq!(
+        client.try_fund_request_card(&lender, &card_id, &5_000_0000000),
+        Err(Ok(LoanError::FundingDeadlinePassed))
+    );
+}
+
+#[test]
+fn test_cancel_request_card_refunds_commitments() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, _, card_id) = setup_request_card(&env);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 20_000_0000000);
+    
+    client.fund_request_card(&lender, &card_id, &5_000_0000000);
+    client.cancel_card(&card_id, &false);
+    
+    assert_eq!(token_client.balance(&lender), 20_000_0000000);
+}
+
+#[test]
+fn test_buy_position_redirects_installments() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let buyer = Address::generate(&env);
+    mint(&env, &setup.token.address, &buyer, 11_500_0000000);
+    
+    client.list_position(&setup.investor, &setup.loan_id, &11_500_0000000);
+    let listing_ttl = env.as_contract(&client.address, || {
+        env.storage().persistent().get_ttl(&("LISTING", setup.loan_id, setup.investor.clone()))
+    });
+    assert!(listing_ttl >= 100 * 17280);
+    assert_eq!(client.get_listing(&setup.loan_id, &setup.investor).unwrap().price, 11_500_0000000);
+    
+    client.buy_position(&buyer, &setup.loan_id, &setup.investor);
+    
+    assert_eq!(setup.token.balance(&setup.investor), 11_500_0000000);
+    assert_eq!(setup.token.balance(&buyer), 0);
+    assert!(client.get_listing(&setup.loan_id, &setup.investor).is_none());
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.lender, buyer);
+    
+    let history = client.get_ownership_history(&setup.loan_id).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.get(0).unwrap().from, setup.investor);
+    assert_eq!(history.get(0).unwrap().to, buyer);
+    assert_eq!(history.get(0).unwrap().amount, 12_000_0000000);
+    
+    // As próximas parcelas vão para o novo titular
+    client.make_payment(&setup.loan_id, &1_000_0000000);
+    assert_eq!(setup.token.balance(&buyer), 1_000_0000000);
+    assert_eq!(setup.token.balance(&setup.investor), 11_500_0000000);
+}
+
+#[test]
+fn test_buy_unlisted_position() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let buyer = Address::generate(&env);
+    mint(&env, &setup.token.address, &buyer, 11_500_0000000);
+    
+    setup.client.list_position(&setup.investor, &setup.loan_id, &11_500_0000000);
+    setup.client.cancel_listing(&setup.investor, &setup.loan_id);
+    
+    assert_eq!(
+        setup.client.try_buy_position(&buyer, &setup.loan_id, &setup.investor),
+        Err(Ok(LoanError::ListingNotFound))
+    );
+}
+
+#[test]
+fn test_list_position_without_holding() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let stranger = Address::generate(&env);
+    
+    assert_eq!(
+        setup.client.try_list_position(&stranger, &setup.loan_id, &1_000_0000000),
+        Err(Ok(LoanError::PositionNotFound))
+    );
+}
+
+#[test]
+fn test_accept_restructuring_rebuilds_schedule() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    client.make_payment(&setup.loan_id, &2_000_0000000);
+    
+    client.propose_restructuring(&setup.loan_id, &0, &20, &Vec::new(&env));
+    assert_eq!(client.get_restructuring_proposal(&setup.loan_id).unwrap().status, ProposalStatus::Pending);
+    
+    assert!(client.accept_restructuring(&setup.investor, &setup.loan_id));
+    
+    let loan = client.get_loan(&setup.loan_id).unwrap();
+    assert_eq!(loan.installments, 22);
+    assert_eq!(loan.paid_installments, 2);
+    assert_eq!(loan.installment_amount, 500_0000000);
+    assert_eq!(client.get_outstanding_balance(&setup.loan_id), 10_000_0000000);
+    
+    let schedule = client.get_schedule(&setup.loan_id).unwrap();
+    assert_eq!(schedule.len(), 22);
+    assert_eq!(schedule.get(2).unwrap().number, 3);
+    assert_eq!(schedule.get(2).unwrap().amount, 500_0000000);
+    
+    // Condições originais ficam guardadas para auditoria
+    let history = client.get_terms_history(&setup.loan_id).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.get(0).unwrap().installments, 12);
+    assert_eq!(history.get(0).unwrap().total_due, 12_000_0000000);
+    
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    let renegotiations = score_client.get_renegotiation_history(&setup.borrower).unwrap();
+    assert_eq!(renegotiations.get(0).unwrap().loan_id, setup.loan_id);
+}
+
+#[test]
+fn test_restructuring_requires_every_share_holder() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, borrower, card_id) = setup_request_card(&env);
+    let lender_a = Address::generate(&env);
+    let lender_b = Address::generate(&env);
+    mint(&env, &token_client.address, &lender_a, 20_000_0000000);
+    mint(&env, &token_client.address, &lender_b, 20_000_0000000);
+    client.fund_request_card(&lender_a, &card_id, &15_000_0000000);
+    let loan_id = client.fund_request_card(&lender_b, &card_id, &5_000_0000000).unwrap();
+    disburse(&env, &client, loan_id);
+    
+    client.propose_restructuring(&loan_id, &0, &20, &Vec::new(&env));
+    assert_eq!(
+        client.try_accept_restructuring(&borrower, &loan_id),
+        Err(Ok(LoanError::PositionNotFound))
+    );
+    
+    // O credor líder sozinho não aplica a proposta, mesmo aceitando duas vezes
+    assert!(!client.accept_restructuring(&lender_a, &loan_id));
+    assert!(!client.accept_restructuring(&lender_a, &loan_id));
+    assert_eq!(client.get_restructuring_approvals(&loan_id), vec![&env, lender_a.clone()]);
+    assert_eq!(client.get_restructuring_proposal(&loan_id).unwrap().status, ProposalStatus::Pending);
+    assert_eq!(client.get_loan(&loan_id).unwrap().installments, 10);
+    
+    // Com o aceite do último credor o cronograma é refeito
+    assert!(client.accept_restructuring(&lender_b, &loan_id));
+    assert_eq!(client.get_restructuring_proposal(&loan_id).unwrap().status, ProposalStatus::Accepted);
+    assert_eq!(client.get_loan(&loan_id).unwrap().installments, 20);
+    assert_eq!(client.get_restructuring_approvals(&loan_id).len(), 0);
+    
+    // Um credor minoritário pode recusar a proposta seguinte
+    client.propose_restructuring(&loan_id, &0, &24, &Vec::new(&env));
+    client.accept_restructuring(&lender_a, &loan_id);
+    client.reject_restructuring(&lender_b, &loan_id);
+    assert_eq!(client.get_restructuring_proposal(&loan_id).unwrap().status, ProposalStatus::Rejected);
+    assert_eq!(client.get_loan(&loan_id).unwrap().installments, 20);
+}
+
+#[test]
+fn test_reject_restructuring_keeps_terms() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    client.propose_restructuring(&setup.loan_id, &0, &24, &Vec::new(&env));
+    assert_eq!(
+        client.try_reject_restructuring(&setup.borrower, &setup.loan_id),
+        Err(Ok(LoanError::PositionNotFound))
+    );
+    client.reject_restructuring(&setup.investor, &setup.loan_id);
+    
+    assert_eq!(client.get_restructuring_proposal(&setup.loan_id).unwrap().status, ProposalStatus::Rejected);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().installments, 12);
+    assert!(client.get_terms_history(&setup.loan_id).is_none());
+}
+
+#[test]
+fn test_propose_restructuring_twice() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    
+    setup.client.propose_restructuring(&setup.loan_id, &0, &24, &Vec::new(&env));
+    assert_eq!(
+        setup.client.try_propose_restructuring(&setup.loan_id, &0, &18, &Vec::new(&env)),
+        Err(Ok(LoanError::ProposalAlreadyPending))
+    );
+}
+
+fn setup_application<'a>(env: &Env) -> (LoanContractClient<'a>, Address, u64) {
+    let admin = Address::generate(env);
+    let token_admin = Address::generate(env);
+    let token_client = create_token_contract(env, &token_admin);
+    let governance = create_governance_contract(env, &admin, &token_client.address);
+    let credit_score = create_credit_score_contract(env, &admin);
+    let investor = Address::generate(env);
+    let borrower = Address::generate(env);
+    
+    let (contract_id, client) = create_loan_contract(env);
+    
+    client.initialize(&admin, &token_client.address, &governance, &credit_score);
+    CreditScoreContractClient::new(env, &credit_score).set_loan_contract(&contract_id);
+    GovernanceContractClient::new(env, &governance).set_loan_contract(&contract_id);
+    give_credit_score(env, &credit_score, &borrower);
+    
+    mint(env, &token_client.address, &investor, 100_000_0000000);
+    
+    let card_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &100_000_0000000,
+        &1_000_0000000,
+        &0,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    let app_id = client.apply_to_investment_card(&borrower, &card_id, &10_000_0000000);
+    (client, borrower, app_id)
+}
+
+#[test]
+fn test_reject_application_with_reason() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, borrower, app_id) = setup_application(&env);
+    
+    client.reject_application(&app_id, &Some(3));
+    
+    let application = client.get_application(&app_id).unwrap();
+    assert_eq!(application.status, ApplicationStatus::Rejected);
+    assert_eq!(application.reason_code, Some(3));
+    assert_eq!(application.applicant, borrower);
+    assert_eq!(client.get_card_applications(&application.card_id).len(), 1);
+}
+
+#[test]
+fn test_approve_withdrawn_application() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, _, app_id) = setup_application(&env);
+    
+    client.withdraw_application(&app_id);
+    assert_eq!(client.get_application_status(&app_id), Some(ApplicationStatus::Withdrawn));
+    
+    assert_eq!(
+        client.try_approve_application(&app_id, &12, &Vec::new(&env)),
+        Err(Ok(LoanError::ApplicationNotPending))
+    );
+}
+
+#[test]
+fn test_application_expires_after_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, _, app_id) = setup_application(&env);
+    assert_eq!(client.get_application_status(&app_id), Some(ApplicationStatus::Pending));
+    
+    let ttl = client.get_application_ttl();
+    env.ledger().with_mut(|li| li.timestamp += ttl + 1);
+    
+    assert_eq!(client.get_application_status(&app_id), Some(ApplicationStatus::Expired));
+    assert_eq!(
+        client.try_approve_application(&app_id, &12, &Vec::new(&env)),
+        Err(Ok(LoanError::ApplicationExpired))
+    );
+    
+    client.expire_application(&app_id);
+    assert_eq!(client.get_application(&app_id).unwrap().status, ApplicationStatus::Expired);
+}
+
+#[test]
+fn test_expire_application_before_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, _, app_id) = setup_application(&env);
+    
+    assert_eq!(
+        client.try_expire_application(&app_id),
+        Err(Ok(LoanError::ApplicationNotExpired))
+    );
+}
+
+#[test]
+fn test_list_loans_by_borrower_and_status() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    
+    // Segundo empréstimo do mesmo tomador, no mesmo card (ID 1)
+    let app_id = client.apply_to_investment_card(&setup.borrower, &1, &3_000_0000000);
+    let second_id = client.approve_application(&app_id, &3, &Vec::new(&env));
+    disburse(&env, &client, second_id);
+    
+    let loans = client.list_loans_by_borrower(&setup.borrower, &None, &0, &10);
+    assert_eq!(loans.len(), 2);
+    assert_eq!(loans.get(0).unwrap().id, setup.loan_id);
+    assert_eq!(loans.get(1).unwrap().id, second_id);
+    
+    // Paginação
+    let page = client.list_loans_by_borrower(&setup.borrower, &None, &1, &10);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().id, second_id);
+    
+    client.pay_off_loan(&second_id);
+    
+    let active = client.list_loans_by_borrower(&setup.borrower, &Some(LoanStatus::Active), &0, &10);
+    assert_eq!(active.len(), 1);
+    assert_eq!(active.get(0).unwrap().id, setup.loan_id);
+    
+    let completed = client.list_loans_by_status(&LoanStatus::Completed, &0, &10);
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed.get(0).unwrap().id, second_id);
+    assert_eq!(client.list_loans_by_status(&LoanStatus::Active, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_missing_records_return_errors_instead_of_panicking() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+
+    // Cronograma ausente (ex.: entrada arquivada): o pagamento falha com erro tipado
+    env.as_contract(&client.address, || {
+        env.storage().persistent().remove(&("SCHEDULE", setup.loan_id));
+    });
+    assert_eq!(
+        client.try_make_payment(&setup.loan_id, &1_000_0000000),
+        Err(Ok(LoanError::LoanNotFound))
+    );
+
+    // Registro do empréstimo ausente: as listagens o ignoram
+    env.as_contract(&client.address, || {
+        env.storage().persistent().remove(&("LOAN", setup.loan_id));
+    });
+    assert_eq!(client.list_loans_by_borrower(&setup.borrower, &None, &0, &10).len(), 0);
+    assert_eq!(client.list_loans_by_status(&LoanStatus::Active, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_list_loans_by_lender_follows_position_sale() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let buyer = Address::generate(&env);
+    mint(&env, &setup.token.address, &buyer, 11_500_0000000);
+    
+    assert_eq!(client.list_loans_by_lender(&setup.investor, &None, &0, &10).len(), 1);
+    
+    client.list_position(&setup.investor, &setup.loan_id, &11_500_0000000);
+    client.buy_position(&buyer, &setup.loan_id, &setup.investor);
+    
+    assert_eq!(client.list_loans_by_lender(&setup.investor, &None, &0, &10).len(), 0);
+    assert_eq!(client.list_loans_by_lender(&buyer, &None, &0, &10).get(0).unwrap().id, setup.loan_id);
+}
+
+#[test]
+fn test_list_active_and_open_cards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, _, card_id) = setup_request_card(&env);
+    let investor = Address::generate(&env);
+    mint(&env, &token_client.address, &investor, 20_000_0000000);
+    
+    let investment_id = client.create_investment_card(
+        &investor,
+        &token_client.address,
+        &10_000_0000000,
+        &1_000_0000000,
+        &0,
+        &12,
+        &40,
+        &AmortizationMethod::Price,
+        &None,
+    );
+    
+    assert_eq!(client.list_open_request_cards(&0, &10).get(0).unwrap().id, card_id);
+    assert_eq!(client.list_active_investment_cards(&0, &10).get(0).unwrap().id, investment_id);
+    
+    // Cards com captação encerrada ou cancelados deixam as listagens
+    env.ledger().with_mut(|li| li.timestamp += 8 * 86400);
+    assert_eq!(client.list_open_request_cards(&0, &10).len(), 0);
+    
+    client.cancel_card(&investment_id, &true);
+    assert_eq!(client.list_active_investment_cards(&0, &10).len(), 0);
+}
+
+#[test]
+fn test_loan_state_survives_ttl_with_keeper() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    client.make_payment(&setup.loan_id, &1_000_0000000);
+    
+    let ttl = env.as_contract(&client.address, || {
+        env.storage().persistent().get_ttl(&("LOAN", setup.loan_id))
+    });
+    assert!(ttl >= 100 * 17280);
+    
+    let mut loan_ids = Vec::new(&env);
+    loan_ids.push_back(setup.loan_id);
+    let mut users = Vec::new(&env);
+    users.push_back(setup.borrower.clone());
+    
+    // Keeper renova a cada ~25 dias; após 150 dias as entradas originais já teriam sido arquivadas
+    for _ in 0..6 {
+        env.ledger().with_mut(|li| li.sequence_number += 25 * 17280);
+        client.extend_ttl(&loan_ids, &Vec::new(&env));
+        score_client.extend_ttl(&users);
+    }
+    
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+    assert_eq!(client.get_schedule(&setup.loan_id).unwrap().len(), 12);
+    assert_eq!(client.get_payment_history(&setup.loan_id).unwrap().len(), 1);
+    assert_eq!(client.get_loan_shares(&setup.loan_id).unwrap().len(), 1);
+    assert_eq!(score_client.get_payment_history(&setup.borrower).unwrap().len(), 1);
+    assert!(score_client.get_score(&setup.borrower).is_some());
+}
+
+#[test]
+fn test_reads_extend_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    client.make_payment(&setup.loan_id, &1_000_0000000);
+
+    // Sem keeper nem escritas: as consultas periódicas bastam para manter as entradas vivas
+    for _ in 0..6 {
+        env.ledger().with_mut(|li| li.sequence_number += 25 * 17280);
+        assert!(client.get_loan(&setup.loan_id).is_some());
+        assert!(client.get_schedule(&setup.loan_id).is_some());
+        assert!(score_client.get_score(&setup.borrower).is_some());
+    }
+
+    let (loan_ttl, schedule_ttl) = env.as_contract(&client.address, || {
+        (
+            env.storage().persistent().get_ttl(&("LOAN", setup.loan_id)),
+            env.storage().persistent().get_ttl(&("SCHEDULE", setup.loan_id)),
+        )
+    });
+    assert!(loan_ttl >= 100 * 17280);
+    assert!(schedule_ttl >= 100 * 17280);
+    let score_ttl = env.as_contract(&setup.credit_score, || {
+        env.storage().persistent().get_ttl(&("SCORE", setup.borrower.clone()))
+    });
+    assert!(score_ttl >= 100 * 17280);
+}
+
+#[test]
+fn test_originations_pause_still_accepts_repayments() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    GovernanceContractClient::new(&env, &setup.governance).set_pause(&PauseScope::Originations);
+    
+    mint(&env, &setup.token.address, &setup.investor, 10_000_0000000);
+    assert_eq!(
+        client.try_create_investment_card(
+            &setup.investor,
+            &setup.token.address,
+            &10_000_0000000,
+            &1_000_0000000,
+            &0,
+            &12,
+            &40,
+            &AmortizationMethod::Price,
+            &None,
+        ),
+        Err(Ok(LoanError::ContractPaused))
+    );
+    
+    // Pagamentos continuam para não empurrar tomadores para a inadimplência
+    client.make_payment(&setup.loan_id, &1_000_0000000);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+}
+
+#[test]
+fn test_full_pause_blocks_repayments_until_lifted() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let governance = GovernanceContractClient::new(&env, &setup.governance);
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    score_client.set_governance(&setup.governance);
+    
+    governance.set_pause(&PauseScope::All);
+    assert_eq!(
+        client.try_make_payment(&setup.loan_id, &1_000_0000000),
+        Err(Ok(LoanError::ContractPaused))
+    );
+    let off_chain_data = OffChainData {
+        bank_statements: true,
+        pix_history: true,
+        invoices: true,
+        credit_bureau: true,
+    };
+    assert_eq!(
+        score_client.try_update_credit_score(&setup.borrower, &off_chain_data, &1000).err(),
+        Some(Ok(CreditScoreError::ContractPaused))
+    );
+    
+    governance.set_pause(&PauseScope::None);
+    client.make_payment(&setup.loan_id, &1_000_0000000);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+}
+
+#[test]
+fn test_migrate_loans_from_v1_layout_in_batches() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let lender = Address::generate(&env);
+    let payment_id = String::from_str(&env, "pix-001");
+    client.register_pix_payment(&payment_id, &String::from_str(&env, "pedido-001"), &1_000_0000000, &PixType::Repayment, &1);
+    
+    // Simula um contrato implantado no layout 1: dois empréstimos antigos, sem versão
+    // do schema nem configuração de encargos; o empréstimo 1, o card 1 e o PIX ficam
+    // no layout 2, sem ativo
+    env.as_contract(&client.address, || {
+        for key in [("LOAN", 1u64), ("INV_CARD", 1u64)] {
+            let mut fields: Map<Symbol, Val> = env.storage().persistent().get(&key).unwrap();
+            fields.remove(Symbol::new(&env, "asset"));
+            env.storage().persistent().set(&key, &fields);
+        }
+        let pix_key = ("PIX_PAYMENT", payment_id.clone());
+        let mut pix: Map<Symbol, Val> = env.storage().persistent().get(&pix_key).unwrap();
+        pix.remove(Symbol::new(&env, "asset"));
+        env.storage().persistent().set(&pix_key, &pix);
+        for loan_id in 2..4u64 {
+            let loan = LoanV1 {
+                id: loan_id,
+                borrower: setup.borrower.clone(),
+                lender: lender.clone(),
+                amount: 1_200_0000000,
+                interest_rate: 1000,
+                installments: 12,
+                installment_amount: 110_0000000,
+                paid_installments: 1,
+                total_paid: 110_0000000,
+                status: LoanStatus::Active,
+                created_at: 0,
+                next_payment_date: 60 * 86400,
+                payment_dates: Vec::new(&env),
+            };
+            let mut payments = Vec::new(&env);
+            payments.push_back(PaymentV1 {
+                loan_id,
+                installment_number: 1,
+                amount: 110_0000000,
+                paid_at: 20 * 86400,
+                was_on_time: true,
+            });
+            env.storage().persistent().set(&("LOAN", loan_id), &loan);
+            env.storage().persistent().set(&("PAYMENTS", loan_id), &payments);
+        }
+        env.storage().instance().set(&"NEXT_LOAN_ID", &4u64);
+        env.storage().instance().remove(&"SCHEMA_VERSION");
+        env.storage().instance().remove(&"LATE_FEE_CONFIG");
+        env.storage().instance().remove(&"ASSETS");
+    });
+    assert_eq!(client.get_schema_version(), 1);
+    
+    // O empréstimo 1 já tem o cronograma do layout 2 e só ganha o ativo
+    assert_eq!(client.migrate(&2), 1);
+    assert_eq!(client.get_schema_version(), 1);
+    assert_eq!(client.migrate(&2), 0);
+    assert_eq!(client.get_schema_version(), 3);
+    assert_eq!(client.migrate(&2), 0);
+    
+    let loan = client.get_loan(&1).unwrap();
+    assert_eq!(loan.total_due, 12_000_0000000);
+    assert_eq!(loan.asset, setup.token.address);
+    assert_eq!(client.get_investment_card(&1).unwrap().asset, setup.token.address);
+    assert_eq!(client.get_assets(), vec![&env, setup.token.address.clone()]);
+    // PIX antigos não são enumeráveis e recebem o ativo padrão na leitura
+    assert_eq!(client.get_pix_payment(&payment_id).unwrap().asset, setup.token.address);
+    let loan = client.get_loan(&2).unwrap();
+    assert_eq!(loan.asset, setup.token.address);
+    assert_eq!(loan.amortization, AmortizationMethod::Price);
+    assert_eq!(loan.total_due, 1_320_0000000);
+    assert_eq!(loan.paid_installments, 1);
+    assert_eq!(client.get_schedule(&2).unwrap().len(), 12);
+    assert_eq!(client.get_loan_shares(&2).unwrap().get(0).unwrap().lender, lender);
+    assert_eq!(client.get_payment_history(&2).unwrap().get(0).unwrap().late_fee, 0);
+    assert_eq!(client.list_loans_by_lender(&lender, &None, &0, &10).len(), 2);
+    
+    // Após a migração o empréstimo antigo segue o fluxo normal de pagamento
+    mint(&env, &setup.token.address, &setup.borrower, 110_0000000);
+    client.make_payment(&2, &110_0000000);
+    assert_eq!(client.get_loan(&2).unwrap().paid_installments, 2);
+    assert_eq!(setup.token.balance(&lender), 110_0000000);
+}
+
+#[test]
+fn test_migrate_investment_cards_from_v1_layout_in_batches() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let investor = Address::generate(&env);
+    
+    // Cards 2 e 3 gravados no layout 1, sem custódia, método de amortização nem ativo
+    env.as_contract(&client.address, || {
+        for (card_id, is_active) in [(2u64, true), (3u64, false)] {
+            let card = InvestmentCardV1 {
+                id: card_id,
+                investor: investor.clone(),
+                max_amount: 5_000_0000000,
+                min_amount: 1_000_0000000,
+                interest_rate: 1000,
+                max_installments: 12,
+                target_risk_level: 40,
+                is_active,
+                total_invested: 0,
+                created_at: 0,
+            };
+            env.storage().persistent().set(&("INV_CARD", card_id), &card);
+        }
+        env.storage().instance().set(&"NEXT_CARD_ID", &4u64);
+        env.storage().instance().remove(&"SCHEMA_VERSION");
+    });
+    
+    assert_eq!(client.migrate(&2), 1);
+    assert_eq!(client.migrate(&2), 0);
+    assert_eq!(client.get_schema_version(), 3);
+    
+    let card = client.get_investment_card(&2).unwrap();
+    assert_eq!(card.asset, setup.token.address);
+    assert_eq!(card.amortization, AmortizationMethod::Price);
+    assert_eq!(card.escrowed_amount, 0);
+    assert_eq!(card.interest_rate, 1000);
+    assert!(!client.get_investment_card(&3).unwrap().is_active);
+    let active = client.list_active_investment_cards(&0, &10);
+    assert_eq!(active.len(), 2);
+    assert_eq!(active.get(1).unwrap().id, 2);
+    
+    // Sem capital em custódia o card não aceita aplicações e pode ser encerrado
+    assert_eq!(
+        client.try_apply_to_investment_card(&setup.borrower, &2, &1_000_0000000),
+        Err(Ok(LoanError::InsufficientEscrow))
+    );
+    client.cancel_card(&2, &true);
+    assert!(!client.get_investment_card(&2).unwrap().is_active);
+    assert_eq!(client.list_active_investment_cards(&0, &10).len(), 1);
+}
+
+#[test]
+fn test_migrate_request_cards_from_v1_layout_in_batches() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let lender = Address::generate(&env);
+    env.ledger().with_mut(|li| li.timestamp = 10 * 86400);
+    
+    // Card 2 aberto e card 3 já financiado, ambos no layout 1
+    env.as_contract(&client.address, || {
+        for (card_id, is_funded) in [(2u64, false), (3u64, true)] {
+            let card = RequestCardV1 {
+                id: card_id,
+                borrower: setup.borrower.clone(),
+                requested_amount: 2_000_0000000,
+                desired_installments: 6,
+                preferred_payment_dates: Vec::new(&env),
+                description: String::from_str(&env, "Capital de giro"),
+                is_active: !is_funded,
+                is_funded,
+                created_at: 86400,
+            };
+            env.storage().persistent().set(&("REQ_CARD", card_id), &card);
+        }
+        env.storage().instance().set(&"NEXT_CARD_ID", &4u64);
+        env.storage().instance().remove(&"SCHEMA_VERSION");
+    });
+    
+    assert_eq!(client.migrate(&2), 1);
+    assert_eq!(client.migrate(&2), 0);
+    assert_eq!(client.get_schema_version(), 3);
+    
+    let card = client.get_request_card(&2).unwrap();
+    assert_eq!(card.asset, setup.token.address);
+    assert_eq!(card.amortization, AmortizationMethod::Price);
+    assert_eq!(card.interest_rate, 0);
+    assert_eq!(card.funded_amount, 0);
+    assert_eq!(card.funding_deadline, 86400);
+    assert_eq!(client.get_request_card(&3).unwrap().funded_amount, 2_000_0000000);
+    
+    // O pedido sem taxa não pode ser financiado; o tomador o encerra e publica outro
+    mint(&env, &setup.token.address, &lender, 2_000_0000000);
+    assert_eq!(
+        client.try_fund_request_card(&lender, &2, &2_000_0000000),
+        Err(Ok(LoanError::FundingDeadlinePassed))
+    );
+    assert_eq!(client.list_open_request_cards(&0, &10).len(), 0);
+    client.cancel_card(&2, &false);
+    assert!(!client.get_request_card(&2).unwrap().is_active);
+}
+
+#[test]
+fn test_migrate_applications_from_v1_layout_in_batches() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let applicant = Address::generate(&env);
+    
+    // Aplicações 2 (pendente) e 3 (rejeitada) ao card 1, gravadas no layout 1
+    env.as_contract(&client.address, || {
+        for (app_id, status) in [(2u64, ApplicationStatus::Pending), (3u64, ApplicationStatus::Rejected)] {
+            let application = LoanApplicationV1 {
+                id: app_id,
+                card_id: 1,
+                card_type: CardType::Investment,
+                applicant: applicant.clone(),
+                amount: 1_000_0000000,
+                status,
+                created_at: 86400,
+            };
+            env.storage().persistent().set(&("APP", app_id), &application);
+        }
+        env.storage().instance().set(&"NEXT_APP_ID", &4u64);
+        env.storage().instance().remove(&"SCHEMA_VERSION");
+    });
+    
+    assert_eq!(client.migrate(&1), 2);
+    assert_eq!(client.migrate(&1), 1);
+    assert_eq!(client.migrate(&1), 0);
+    assert_eq!(client.get_schema_version(), 3);
+    
+    let application = client.get_application(&2).unwrap();
+    assert_eq!(application.reason_code, None);
+    assert_eq!(application.expires_at, 86400 + client.get_application_ttl());
+    assert_eq!(client.get_application(&3).unwrap().status, ApplicationStatus::Rejected);
+    assert_eq!(client.get_card_applications(&1), vec![&env, 1u64, 2, 3]);
+    
+    // A aplicação pendente segue o fluxo atual: prazo de decisão e rejeição pelo investidor
+    let ttl = client.get_application_ttl();
+    env.ledger().with_mut(|li| li.timestamp = 86400 + ttl + 1);
+    assert_eq!(client.get_application_status(&2), Some(ApplicationStatus::Expired));
+    client.expire_application(&2);
+    assert_eq!(client.get_application(&2).unwrap().status, ApplicationStatus::Expired);
+}
+
+#[test]
+fn test_upgrade_requires_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    env.set_auths(&[]);
+    
+    let hash = BytesN::from_array(&env, &[0; 32]);
+    assert!(setup.client.try_upgrade(&hash).is_err());
+    assert!(setup.client.try_migrate(&10).is_err());
+    assert_eq!(setup.client.get_schema_version(), 3);
+}
+
+#[test]
+fn test_pix_register_rejects_duplicate_ids() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let payment_id = String::from_str(&env, "pix-001");
+    let order_id = String::from_str(&env, "pedido-001");
+    client.register_pix_payment(&payment_id, &order_id, &1_000_0000000, &PixType::Repayment, &setup.loan_id);
+    
+    assert_eq!(
+        client.try_register_pix_payment(
+            &payment_id,
+            &String::from_str(&env, "pedido-002"),
+            &1_000_0000000,
+            &PixType::Repayment,
+            &setup.loan_id,
+        ),
+        Err(Ok(LoanError::DuplicatePixPayment))
+    );
+    assert_eq!(
+        client.try_register_pix_payment(
+            &String::from_str(&env, "pix-002"),
+            &order_id,
+            &1_000_0000000,
+            &PixType::Repayment,
+            &setup.loan_id,
+        ),
+        Err(Ok(LoanError::DuplicatePixOrder))
+    );
+}
+
+#[test]
+fn test_pix_repayment_completion_is_idempotent() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let payment_id = String::from_str(&env, "pix-001");
+    client.register_pix_payment(
+        &payment_id,
+        &String::from_str(&env, "pedido-001"),
+        &1_000_0000000,
+        &PixType::Repayment,
+        &setup.loan_id,
+    );
+    setup.token.approve(&setup.borrower, &client.address, &1_000_0000000, &(env.ledger().sequence() + 1000));
+    
+    client.update_pix_payment_status(&payment_id, &PixStatus::Processing);
+    client.update_pix_payment_status(&payment_id, &PixStatus::Completed);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+    
+    // Webhook reenviado não paga a parcela de novo
+    client.update_pix_payment_status(&payment_id, &PixStatus::Completed);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+    // 12k recebidos no empréstimo, menos uma parcela
+    assert_eq!(setup.token.balance(&setup.borrower), 11_000_0000000);
+    
+    // Estados finais não mudam
+    assert_eq!(
+        client.try_update_pix_payment_status(&payment_id, &PixStatus::Failed),
+        Err(Ok(LoanError::InvalidPixTransition))
+    );
+    
+    let history = client.get_pix_status_history(&payment_id);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.get(0).unwrap().status, PixStatus::Pending);
+    assert_eq!(history.get(1).unwrap().status, PixStatus::Processing);
+    assert_eq!(history.get(2).unwrap().status, PixStatus::Completed);
+}
+
+fn sign_attestation(env: &Env, key: &SigningKey, contract: &Address, attestation: &PixAttestation) -> BytesN<64> {
+    let message: std::vec::Vec<u8> = (contract.clone(), attestation.clone()).to_xdr(env).iter().collect();
+    BytesN::from_array(env, &key.sign(&message).to_bytes())
+}
+
+#[test]
+fn test_pix_attestation_relayed_by_anyone() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let oracle_key = SigningKey::from_bytes(&[7; 32]);
+    let oracle = BytesN::from_array(&env, &oracle_key.verifying_key().to_bytes());
+    client.add_pix_oracle(&oracle);
+    
+    let payment_id = String::from_str(&env, "pix-001");
+    let order_id = String::from_str(&env, "pedido-001");
+    client.register_pix_payment(&payment_id, &order_id, &1_000_0000000, &PixType::Repayment, &setup.loan_id);
+    
+    // Atestado com valor diferente do registrado
+    let wrong_amount = PixAttestation {
+        payment_id: payment_id.clone(),
+        order_id: order_id.clone(),
+        amount: 2_000_0000000,
+        status: PixStatus::Completed,
+        timestamp: 1_700_000_000,
+    };
+    assert_eq!(
+        client.try_submit_pix_attestation(&wrong_amount, &oracle, &sign_attestation(&env, &oracle_key, &client.address, &wrong_amount)),
+        Err(Ok(LoanError::PixAttestationMismatch))
+    );
+    
+    let attestation = PixAttestation {
+        amount: 1_000_0000000,
+        ..wrong_amount
+    };
+    let signature = sign_attestation(&env, &oracle_key, &client.address, &attestation);
+    
+    // Chave não registrada e assinatura que não cobre o atestado são recusadas
+    let outsider_key = SigningKey::from_bytes(&[9; 32]);
+    let outsider = BytesN::from_array(&env, &outsider_key.verifying_key().to_bytes());
+    assert_eq!(
+        client.try_submit_pix_attestation(&attestation, &outsider, &sign_attestation(&env, &outsider_key, &client.address, &attestation)),
+        Err(Ok(LoanError::UnknownPixOracle))
+    );
+    let forged = PixAttestation {
+        status: PixStatus::Failed,
+        ..attestation.clone()
+    };
+    assert!(client.try_submit_pix_attestation(&forged, &oracle, &signature).is_err());
+    
+    // Atestado assinado para outra implantação do contrato não é aceito
+    let other_deployment = Address::generate(&env);
+    let replayed = sign_attestation(&env, &oracle_key, &other_deployment, &attestation);
+    assert!(client.try_submit_pix_attestation(&attestation, &oracle, &replayed).is_err());
+    
+    // Sem nenhuma assinatura na retransmissão, o pagamento depende só da autorização do tomador
+    env.set_auths(&[]);
+    assert_eq!(
+        client.try_submit_pix_attestation(&attestation, &oracle, &signature),
+        Err(Ok(LoanError::InsufficientAllowance))
+    );
+    env.mock_all_auths();
+    setup.token.approve(&setup.borrower, &client.address, &1_000_0000000, &(env.ledger().sequence() + 1000));
+    env.set_auths(&[]);
+    
+    client.submit_pix_attestation(&attestation, &oracle, &signature);
+    assert_eq!(client.get_pix_payment(&payment_id).unwrap().status, PixStatus::Completed);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+    
+    // Retransmitir o mesmo atestado não paga de novo
+    client.submit_pix_attestation(&attestation, &oracle, &signature);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().paid_installments, 1);
+    
+    env.mock_all_auths();
+    client.remove_pix_oracle(&oracle);
+    assert_eq!(client.get_pix_oracles().len(), 0);
+}
+
+#[test]
+fn test_credit_payout_activates_loan_from_disbursement_date() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, borrower, card_id) = setup_request_card(&env);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 30_000_0000000);
+    let loan_id = client.fund_request_card(&lender, &card_id, &20_000_0000000).unwrap();
+    
+    assert_eq!(client.get_loan(&loan_id).unwrap().status, LoanStatus::Pending);
+    assert_eq!(
+        client.try_make_payment(&loan_id, &2_000_0000000),
+        Err(Ok(LoanError::LoanNotActive))
+    );
+    
+    let payment_id = String::from_str(&env, "payout-001");
+    assert_eq!(
+        client.try_register_pix_payment(
+            &payment_id,
+            &String::from_str(&env, "pedido-001"),
+            &19_000_0000000,
+            &PixType::CreditPayout,
+            &loan_id,
+        ),
+        Err(Ok(LoanError::InvalidAmount))
+    );
+    client.register_pix_payment(
+        &payment_id,
+        &String::from_str(&env, "pedido-001"),
+        &20_000_0000000,
+        &PixType::CreditPayout,
+        &loan_id,
+    );
+    assert_eq!(
+        client.try_register_pix_payment(
+            &String::from_str(&env, "payout-002"),
+            &String::from_str(&env, "pedido-002"),
+            &20_000_0000000,
+            &PixType::CreditPayout,
+            &loan_id,
+        ),
+        Err(Ok(LoanError::DuplicatePixPayment))
+    );
+    
+    // O PIX cai três dias depois: o cronograma conta a partir do desembolso
+    env.ledger().with_mut(|li| li.timestamp += 3 * 86400);
+    client.update_pix_payment_status(&payment_id, &PixStatus::Processing);
+    client.update_pix_payment_status(&payment_id, &PixStatus::Completed);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.next_payment_date, env.ledger().timestamp() + 30 * 86400);
+    assert_eq!(client.get_schedule(&loan_id).unwrap().get(0).unwrap().due_date, loan.next_payment_date);
+    assert_eq!(token_client.balance(&borrower), 20_000_0000000);
+}
+
+#[test]
+fn test_expire_pending_loan_without_payout() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let token_client = &setup.token;
+    let governance_balance = token_client.balance(&setup.governance);
+    
+    let app_id = client.apply_to_investment_card(&setup.borrower, &1, &2_000_0000000);
+    let investor_balance = token_client.balance(&setup.investor);
+    let invested = client.get_investment_card(&1).unwrap().total_invested;
+    let loan_id = client.approve_application(&app_id, &2, &Vec::new(&env));
+    assert_eq!(token_client.balance(&setup.governance), governance_balance);
+    assert_eq!(client.get_investment_card(&1).unwrap().total_invested, invested + 2_000_0000000);
+    
+    assert_eq!(client.try_expire_pending_loan(&loan_id), Err(Ok(LoanError::PendingLoanNotExpired)));
+    env.ledger().with_mut(|li| li.timestamp += 7 * 86400 + 1);
+    assert_eq!(client.try_expire_pending_loan(&setup.loan_id), Err(Ok(LoanError::LoanNotPending)));
+    
+    // Sem PIX de desembolso no prazo, principal e taxa reservada voltam ao investidor
+    client.expire_pending_loan(&loan_id);
+    assert_eq!(client.get_loan(&loan_id).unwrap().status, LoanStatus::Cancelled);
+    assert_eq!(token_client.balance(&setup.investor), investor_balance + 2_012_0000000);
+    assert_eq!(token_client.balance(&setup.governance), governance_balance);
+    // O card deixa de contar o principal já devolvido
+    assert_eq!(client.get_investment_card(&1).unwrap().total_invested, invested);
+    
+    // Com o desembolso já registrado, o empréstimo aguarda o resultado do PIX
+    let app_id = client.apply_to_investment_card(&setup.borrower, &1, &2_000_0000000);
+    let loan_id = client.approve_application(&app_id, &2, &Vec::new(&env));
+    client.register_pix_payment(
+        &String::from_str(&env, "payout-late"),
+        &String::from_str(&env, "pedido-late"),
+        &2_000_0000000,
+        &PixType::CreditPayout,
+        &loan_id,
+    );
+    env.ledger().with_mut(|li| li.timestamp += 7 * 86400 + 1);
+    assert_eq!(client.try_expire_pending_loan(&loan_id), Err(Ok(LoanError::PayoutAlreadyRegistered)));
+}
+
+#[test]
+fn test_expire_pending_request_card_loan_resets_card() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, _, card_id) = setup_request_card(&env);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 30_000_0000000);
+    let loan_id = client.fund_request_card(&lender, &card_id, &20_000_0000000).unwrap();
+    assert!(client.get_request_card(&card_id).unwrap().is_funded);
+    
+    env.ledger().with_mut(|li| li.timestamp += 7 * 86400 + 1);
+    client.expire_pending_loan(&loan_id);
+    assert_eq!(token_client.balance(&lender), 30_000_0000000);
+    
+    // A captação devolvida não fica registrada no card, que é encerrado
+    let card = client.get_request_card(&card_id).unwrap();
+    assert!(!card.is_funded);
+    assert!(!card.is_active);
+    assert_eq!(card.funded_amount, 0);
+    assert!(client.get_funding_commitments(&card_id).is_none());
+    assert_eq!(client.list_open_request_cards(&0, &10).len(), 0);
+}
+
+#[test]
+fn test_failed_credit_payout_cancels_loan_and_refunds_lenders() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let (client, token_client, _, borrower, card_id) = setup_request_card(&env);
+    let lender_a = Address::generate(&env);
+    let lender_b = Address::generate(&env);
+    mint(&env, &token_client.address, &lender_a, 20_000_0000000);
+    mint(&env, &token_client.address, &lender_b, 20_000_0000000);
+    client.fund_request_card(&lender_a, &card_id, &12_000_0000000);
+    let loan_id = client.fund_request_card(&lender_b, &card_id, &8_000_0000000).unwrap();
+    
+    let payment_id = String::from_str(&env, "payout-001");
+    client.register_pix_payment(
+        &payment_id,
+        &String::from_str(&env, "pedido-001"),
+        &20_000_0000000,
+        &PixType::CreditPayout,
+        &loan_id,
+    );
+    client.update_pix_payment_status(&payment_id, &PixStatus::Failed);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Cancelled);
+    assert_eq!(client.list_loans_by_status(&LoanStatus::Pending, &0, &10).len(), 0);
+    
+    // Principal e taxas de originação reservadas voltam aos credores
+    assert_eq!(token_client.balance(&lender_a), 20_000_0000000);
+    assert_eq!(token_client.balance(&lender_b), 20_000_0000000);
+    assert_eq!(token_client.balance(&borrower), 0);
+    
+    assert_eq!(
+        client.try_register_pix_payment(
+            &String::from_str(&env, "payout-002"),
+            &String::from_str(&env, "pedido-002"),
+            &20_000_0000000,
+            &PixType::CreditPayout,
+            &loan_id,
+        ),
+        Err(Ok(LoanError::LoanNotPending))
+    );
+}
+
+#[test]
+fn test_card_in_allowed_asset_runs_loan_in_that_asset() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let usdc = create_token_contract(&env, &Address::generate(&env));
+    let investor = Address::generate(&env);
+    mint(&env, &usdc.address, &investor, 10_000_0000000);
+    
+    assert_eq!(
+        client.try_create_investment_card(&investor, &usdc.address, &10_000_0000000, &1_000_0000000, &0, &10, &40, &AmortizationMethod::Price, &None),
+        Err(Ok(LoanError::AssetNotAllowed))
+    );
+    
+    client.add_asset(&usdc.address);
+    assert_eq!(client.get_assets(), vec![&env, setup.token.address.clone(), usdc.address.clone()]);
+    let card_id = client.create_investment_card(&investor, &usdc.address, &10_000_0000000, &1_000_0000000, &0, &10, &40, &AmortizationMethod::Price, &None);
+    let app_id = client.apply_to_investment_card(&setup.borrower, &card_id, &5_000_0000000);
+    let loan_id = client.approve_application(&app_id, &10, &Vec::new(&env));
+    disburse(&env, client, loan_id);
+    
+    let loan = client.get_loan(&loan_id).unwrap();
+    assert_eq!(loan.asset, usdc.address);
+    assert_eq!(usdc.balance(&setup.borrower), 5_000_0000000);
+    assert_eq!(client.get_pix_payment(&String::from_str(&env, "payout-2")).unwrap().asset, usdc.address);
+    
+    // Taxas e fundo de proteção no ativo do card
+    let governance_client = GovernanceContractClient::new(&env, &setup.governance);
+    assert_eq!(usdc.balance(&setup.governance), 30_0000000);
+    assert_eq!(governance_client.get_protection_fund(&usdc.address).total_balance, 5_0000000);
+    
+    // Remover o ativo bloqueia novos cards, mas o empréstimo segue nele
+    client.remove_asset(&usdc.address);
+    assert_eq!(
+        client.try_create_request_card(
+            &setup.borrower,
+            &usdc.address,
+            &1_000_0000000,
+            &10,
+            &Vec::new(&env),
+            &AmortizationMethod::Price,
+            &0,
+            &(env.ledger().timestamp() + 86400),
+            &String::from_str(&env, "Capital de giro"),
+            &None,
+        ),
+        Err(Ok(LoanError::AssetNotAllowed))
+    );
+    client.make_payment(&loan_id, &500_0000000);
+    assert_eq!(usdc.balance(&investor), 500_0000000);
+    assert_eq!(usdc.balance(&setup.borrower), 4_500_0000000);
+}
+
+#[test]
+fn test_collateral_locked_on_application_and_released_on_completion() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let token = &setup.token;
+    mint(&env, &token.address, &setup.investor, 10_000_0000000);
+    
+    // Garantia em outro ativo não tem como ser comparada com a dívida
+    let other_token = create_token_contract(&env, &Address::generate(&env));
+    client.add_asset(&other_token.address);
+    let other_terms = CollateralTerms { asset: other_token.address.clone(), ratio_bps: 15000 };
+    assert_eq!(
+        client.try_create_investment_card(&setup.investor, &token.address, &10_000_0000000, &1_000_0000000, &0, &2, &40, &AmortizationMethod::Price, &Some(other_terms)),
+        Err(Ok(LoanError::InvalidCollateralTerms))
+    );
+    
+    let terms = CollateralTerms { asset: token.address.clone(), ratio_bps: 15000 };
+    let card_id = client.create_investment_card(&setup.investor, &token.address, &10_000_0000000, &1_000_0000000, &0, &2, &40, &AmortizationMethod::Price, &Some(terms));
+    
+    // 150% de 2k travados já na aplicação
+    let app_id = client.apply_to_investment_card(&setup.borrower, &card_id, &2_000_0000000);
+    assert_eq!(token.balance(&setup.borrower), 9_000_0000000);
+    let loan_id = client.approve_application(&app_id, &2, &Vec::new(&env));
+    disburse(&env, client, loan_id);
+    
+    let collateral = client.get_collateral(&loan_id).unwrap();
+    assert_eq!(collateral.owner, setup.borrower);
+    assert_eq!(collateral.amount, 3_000_0000000);
+    
+    // Quitação devolve a garantia
+    client.make_payment(&loan_id, &2_000_0000000);
+    assert_eq!(client.get_loan(&loan_id).unwrap().status, LoanStatus::Completed);
+    assert_eq!(token.balance(&setup.borrower), 12_000_0000000);
+    assert!(client.get_collateral(&loan_id).is_none());
+}
+
+#[test]
+fn test_defaulted_loan_seizes_collateral_before_protection_claim() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let token = &setup.token;
+    let governance_client = GovernanceContractClient::new(&env, &setup.governance);
+    
+    // Garantia de 50% travada ao publicar o pedido de 10k
+    let card_id = client.create_request_card(
+        &setup.borrower,
+        &token.address,
+        &10_000_0000000,
+        &10,
+        &Vec::new(&env),
+        &AmortizationMethod::Price,
+        &0,
+        &(env.ledger().timestamp() + 7 * 86400),
+        &String::from_str(&env, "Reforma da loja"),
+        &Some(CollateralTerms { asset: token.address.clone(), ratio_bps: 5000 }),
+    );
+    assert_eq!(token.balance(&setup.borrower), 7_000_0000000);
+    
+    let lender = Address::generate(&env);
+    mint(&env, &token.address, &lender, 20_000_0000000);
+    let loan_id = client.fund_request_card(&lender, &card_id, &10_000_0000000).unwrap();
+    disburse(&env, client, loan_id);
+    assert_eq!(client.get_collateral(&loan_id).unwrap().amount, 5_000_0000000);
+    
+    // 1k pago; a garantia cobre 5k dos 9k em aberto e o claim fica com o resto
+    client.make_payment(&loan_id, &1_000_0000000);
+    env.ledger().with_mut(|li| li.timestamp += 68 * 86400);
+    let balance = token.balance(&lender);
+    client.mark_as_defaulted(&loan_id);
+    
+    assert_eq!(token.balance(&lender), balance + 5_000_0000000);
+    assert_eq!(client.get_outstanding_balance(&loan_id), 4_000_0000000);
+    assert!(client.get_collateral(&loan_id).is_none());
+    assert_eq!(governance_client.get_claim(&loan_id).unwrap().principal, 4_000_0000000);
+}
+
+#[test]
+fn test_guarantor_covers_missed_installments_after_default() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let token_client = &setup.token;
+    let borrower = &setup.borrower;
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    let guarantor = Address::generate(&env);
+    give_credit_score(&env, &setup.credit_score, &guarantor);
+    
+    let card_id = client.create_request_card(
+        borrower,
+        &token_client.address,
+        &10_000_0000000,
+        &10,
+        &Vec::new(&env),
+        &AmortizationMethod::Price,
+        &0,
+        &(env.ledger().timestamp() + 7 * 86400),
+        &String::from_str(&env, "Notebook para trabalho"),
+        &None,
+    );
+    assert_eq!(client.try_add_guarantor(borrower, &card_id, &false), Err(Ok(LoanError::InvalidGuarantor)));
+    client.add_guarantor(&guarantor, &card_id, &false);
+    assert_eq!(client.try_add_guarantor(&guarantor, &card_id, &false), Err(Ok(LoanError::InvalidGuarantor)));
+    
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 20_000_0000000);
+    let loan_id = client.fund_request_card(&lender, &card_id, &10_000_0000000).unwrap();
+    disburse(&env, client, loan_id);
+    assert_eq!(client.get_guarantors(&loan_id).unwrap(), vec![&env, guarantor.clone()]);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 10_000_0000000);
+    
+    // Uma parcela de 1k paga; com 68 dias a segunda venceu e o empréstimo é inadimplente
+    client.make_payment(&loan_id, &1_000_0000000);
+    env.ledger().with_mut(|li| li.timestamp += 68 * 86400);
+    assert_eq!(client.try_call_guarantors(&lender, &loan_id), Err(Ok(LoanError::LoanNotDefaulted)));
+    client.mark_as_defaulted(&loan_id);
+    let governance_client = GovernanceContractClient::new(&env, &setup.governance);
+    assert_eq!(governance_client.get_claim(&loan_id).unwrap().principal, 9_000_0000000);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 9_000_0000000);
+    
+    // O avalista autorizou só 600 dos 1k vencidos
+    mint(&env, &token_client.address, &guarantor, 5_000_0000000);
+    token_client.approve(&guarantor, &client.address, &600_0000000, &(env.ledger().sequence() + 1000));
+    let balance = token_client.balance(&lender);
+    assert_eq!(client.call_guarantors(&lender, &loan_id), 600_0000000);
+    assert_eq!(token_client.balance(&lender), balance + 600_0000000);
+    assert_eq!(client.get_outstanding_balance(&loan_id), 8_400_0000000);
+    
+    // O valor recuperado sai do claim aberto e da exposição do avalista
+    let claim = governance_client.get_claim(&loan_id).unwrap();
+    assert_eq!(claim.principal, 8_400_0000000);
+    assert_eq!(claim.payout, 6_720_0000000);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 8_400_0000000);
+    
+    token_client.approve(&guarantor, &client.address, &5_000_0000000, &(env.ledger().sequence() + 1000));
+    assert_eq!(client.call_guarantors(&lender, &loan_id), 400_0000000);
+    assert_eq!(governance_client.get_claim(&loan_id).unwrap().principal, 8_000_0000000);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 8_000_0000000);
+    assert_eq!(client.try_call_guarantors(&lender, &loan_id), Err(Ok(LoanError::NoGuaranteeDue)));
+    assert_eq!(client.try_call_guarantors(borrower, &loan_id), Err(Ok(LoanError::PositionNotFound)));
+}
+
+#[test]
+fn test_guarantor_call_capped_by_paid_claim() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let token_client = &setup.token;
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    let governance_client = GovernanceContractClient::new(&env, &setup.governance);
+    let guarantor = Address::generate(&env);
+    give_credit_score(&env, &setup.credit_score, &guarantor);
+    
+    let card_id = client.create_request_card(
+        &setup.borrower,
+        &token_client.address,
+        &10_000_0000000,
+        &10,
+        &Vec::new(&env),
+        &AmortizationMethod::Price,
+        &0,
+        &(env.ledger().timestamp() + 7 * 86400),
+        &String::from_str(&env, "Reforma da loja"),
+        &None,
+    );
+    client.add_guarantor(&guarantor, &card_id, &false);
+    let lender = Address::generate(&env);
+    mint(&env, &token_client.address, &lender, 20_000_0000000);
+    let loan_id = client.fund_request_card(&lender, &card_id, &10_000_0000000).unwrap();
+    disburse(&env, client, loan_id);
+    
+    // Todas as parcelas vencem sem pagamento e o fundo cobre 80% dos 10k
+    env.ledger().with_mut(|li| li.timestamp += 400 * 86400);
+    client.mark_as_defaulted(&loan_id);
+    let donor = Address::generate(&env);
+    mint(&env, &token_client.address, &donor, 10_000_0000000);
+    governance_client.add_to_protection_fund(&donor, &token_client.address, &10_000_0000000);
+    assert_eq!(governance_client.pay_claim(&loan_id), 8_000_0000000);
+    
+    // Os avalistas só respondem pelo que a cobertura não alcançou
+    mint(&env, &token_client.address, &guarantor, 10_000_0000000);
+    token_client.approve(&guarantor, &client.address, &10_000_0000000, &(env.ledger().sequence() + 1000));
+    let balance = token_client.balance(&lender);
+    assert_eq!(client.call_guarantors(&lender, &loan_id), 2_000_0000000);
+    assert_eq!(token_client.balance(&lender), balance + 2_000_0000000);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 0);
+    assert_eq!(client.try_call_guarantors(&lender, &loan_id), Err(Ok(LoanError::NoGuaranteeDue)));
+}
+
+#[test]
+fn test_guarantee_released_when_loan_completes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    let guarantor = Address::generate(&env);
+    mint(&env, &setup.token.address, &setup.investor, 10_000_0000000);
+    
+    let card_id = client.create_investment_card(&setup.investor, &setup.token.address, &10_000_0000000, &1_000_0000000, &0, &2, &40, &AmortizationMethod::Price, &None);
+    let app_id = client.apply_to_investment_card(&setup.borrower, &card_id, &2_000_0000000);
+    
+    // Sem score o avalista só cobre até 5k
+    assert_eq!(client.try_add_guarantor(&guarantor, &99, &true), Err(Ok(LoanError::ApplicationNotFound)));
+    client.add_guarantor(&guarantor, &app_id, &true);
+    let loan_id = client.approve_application(&app_id, &2, &Vec::new(&env));
+    disburse(&env, client, loan_id);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 2_000_0000000);
+    assert!(!score_client.can_borrow(&guarantor, &4_000_0000000));
+    
+    client.make_payment(&loan_id, &2_000_0000000);
+    assert_eq!(score_client.get_guarantee_exposure(&guarantor), 0);
+    assert!(score_client.can_borrow(&guarantor, &4_000_0000000));
+}
+
+#[test]
+fn test_auto_debit_collects_due_installments_and_tips_keeper() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let loan_id = setup.loan_id;
+    let keeper = Address::generate(&env);
+    let loan_ids = vec![&env, loan_id, 99];
+    
+    assert_eq!(client.try_enable_auto_debit(&loan_id, &-1), Err(Ok(LoanError::InvalidAmount)));
+    client.enable_auto_debit(&loan_id, &1_0000000);
+    assert_eq!(client.get_auto_debit(&loan_id), Some(1_0000000));
+    setup.token.approve(&setup.borrower, &client.address, &1_501_0000000, &(env.ledger().sequence() + 1000));
+    
+    // Antes do vencimento nada é cobrado
+    assert_eq!(client.collect_due_installments(&keeper, &loan_ids), Vec::<u64>::new(&env));
+    
+    env.ledger().with_mut(|li| li.timestamp += 30 * 86400);
+    let balance = setup.token.balance(&setup.investor);
+    assert_eq!(client.collect_due_installments(&keeper, &loan_ids), vec![&env, loan_id]);
+    assert_eq!(setup.token.balance(&setup.investor), balance + 1_000_0000000);
+    assert_eq!(setup.token.balance(&keeper), 1_0000000);
+    assert_eq!(client.get_loan(&loan_id).unwrap().paid_installments, 1);
+    
+    // A autorização restante (500) não cobre a segunda parcela: nada é cobrado
+    env.ledger().with_mut(|li| li.timestamp += 30 * 86400);
+    assert_eq!(client.collect_due_installments(&keeper, &loan_ids), Vec::<u64>::new(&env));
+    assert_eq!(client.get_loan(&loan_id).unwrap().paid_installments, 1);
+    
+    client.disable_auto_debit(&loan_id);
+    setup.token.approve(&setup.borrower, &client.address, &5_000_0000000, &(env.ledger().sequence() + 1000));
+    assert_eq!(client.collect_due_installments(&keeper, &loan_ids), Vec::<u64>::new(&env));
+}
+
+// Eventos emitidos até aqui cujo primeiro tópico é `topic`
+fn count_events(env: &Env, topic: &str) -> usize {
+    let topic = Symbol::new(env, topic);
+    env.events().all().iter()
+        .filter(|(_, topics, _)| {
+            topics.get(0).and_then(|value| Symbol::try_from_val(env, &value).ok()) == Some(topic.clone())
+        })
+        .count()
+}
+
+#[test]
+fn test_full_pause_stops_late_fees_and_grace_period() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let governance = GovernanceContractClient::new(&env, &setup.governance);
+    let start = env.ledger().timestamp();
+    
+    // A primeira parcela vence no dia 30; tudo fica pausado do dia 25 ao dia 45
+    env.ledger().with_mut(|li| li.timestamp = start + 25 * 86400);
+    governance.set_pause(&PauseScope::All);
+    env.ledger().with_mut(|li| li.timestamp = start + 45 * 86400);
+    assert_eq!(client.get_late_charges(&setup.loan_id), 0);
+    governance.set_pause(&PauseScope::None);
+    assert_eq!(client.get_late_charges(&setup.loan_id), 0);
+    assert_eq!(client.try_mark_as_defaulted(&setup.loan_id), Err(Ok(LoanError::GracePeriodNotExpired)));
+    
+    // Cinco dias de atraso efetivo: multa de 2% e cinco dias de juros de mora
+    env.ledger().with_mut(|li| li.timestamp = start + 50 * 86400);
+    assert_eq!(client.get_late_charges(&setup.loan_id), 20_0000000 + 1_6666666);
+    assert_eq!(client.process_overdue(&vec![&env, setup.loan_id]).len(), 0);
+    
+    // A carência de 7 dias só se esgota 20 dias depois do que sem a pausa
+    env.ledger().with_mut(|li| li.timestamp = start + 57 * 86400 + 1);
+    assert_eq!(client.process_overdue(&vec![&env, setup.loan_id]), vec![&env, setup.loan_id]);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().status, LoanStatus::Defaulted);
+}
+
+#[test]
+fn test_process_overdue_warns_once_then_defaults_after_grace() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let loan_id = setup.loan_id;
+    let governance_client = GovernanceContractClient::new(&env, &setup.governance);
+    let loan_ids = vec![&env, loan_id, 99];
+    
+    assert_eq!(client.try_set_grace_period(&0), Err(Ok(LoanError::InvalidGracePeriod)));
+    client.set_grace_period(&(3 * 86400));
+    assert_eq!(client.get_grace_period(), 3 * 86400);
+    
+    // Parcela vencida há um dia: aviso emitido uma única vez, sem inadimplência
+    env.ledger().with_mut(|li| li.timestamp += 31 * 86400);
+    assert_eq!(client.process_overdue(&loan_ids), Vec::<u64>::new(&env));
+    assert_eq!(count_events(&env, "overdue"), 1);
+    client.process_overdue(&loan_ids);
+    assert_eq!(count_events(&env, "overdue"), 1);
+    assert_eq!(client.get_loan(&loan_id).unwrap().status, LoanStatus::Active);
+    
+    // Passada a carência configurada o empréstimo entra em inadimplência sem o admin
+    env.ledger().with_mut(|li| li.timestamp += 3 * 86400);
+    assert_eq!(client.process_overdue(&loan_ids), vec![&env, loan_id]);
+    assert_eq!(client.get_loan(&loan_id).unwrap().status, LoanStatus::Defaulted);
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    assert_eq!(score_client.get_default_history(&setup.borrower).unwrap().len(), 1);
+    assert_eq!(governance_client.get_claim(&loan_id).unwrap().principal, 12_000_0000000);
+    assert_eq!(client.process_overdue(&loan_ids), Vec::<u64>::new(&env));
+}
+
+#[test]
+fn test_process_overdue_skips_loan_whose_default_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let setup = setup_active_loan(&env, 12_000_0000000, 12);
+    let client = &setup.client;
+    let app_id = client.apply_to_investment_card(&setup.borrower, &1, &3_000_0000000);
+    let second_id = client.approve_application(&app_id, &3, &Vec::new(&env));
+    disburse(&env, client, second_id);
+
+    // Um claim já aberto para o primeiro empréstimo faz `open_claim` falhar
+    let governance_client = GovernanceContractClient::new(&env, &setup.governance);
+    governance_client.open_claim(&setup.loan_id, &setup.token.address, &1_000_0000000);
+
+    env.ledger().with_mut(|li| li.timestamp += 40 * 86400);
+    let loan_ids = vec![&env, setup.loan_id, second_id];
+    assert_eq!(client.process_overdue(&loan_ids), vec![&env, second_id]);
+    assert_eq!(count_events(&env, "dflt_fail"), 1);
+    assert_eq!(client.get_loan(&setup.loan_id).unwrap().status, LoanStatus::Active);
+    assert_eq!(client.get_loan(&second_id).unwrap().status, LoanStatus::Defaulted);
+    assert_eq!(
+        client.try_mark_as_defaulted(&setup.loan_id),
+        Err(Ok(LoanError::DefaultNotRecorded))
+    );
+
+    // Novas tentativas não duplicam o default já registrado no credit score
+    client.process_overdue(&loan_ids);
+    let score_client = CreditScoreContractClient::new(&env, &setup.credit_score);
+    assert_eq!(score_client.get_default_history(&setup.borrower).unwrap().len(), 2);
+}
@@ -1,9 +1,9 @@
 #![cfg(test)]
 
-use soroban_sdk::{testutils::Address as _, Address, Env};
-use mithril_contracts::credit_score::{CreditScoreContract, CreditScoreContractClient, OffChainData, RiskLevel};
+use soroban_sdk::{testutils::{storage::Persistent as _, Address as _}, Address, Env};
+use mithril_contracts::credit_score::{CreditScoreContract, CreditScoreContractClient, CreditScoreError, OffChainData, RiskLevel};
 
-fn create_contract() -> (Env, Address, CreditScoreContractClient) {
+fn create_contract<'a>() -> (Env, Address, CreditScoreContractClient<'a>) {
     let env = Env::default();
     env.mock_all_auths();
     
@@ -24,6 +24,17 @@ fn test_initialize() {
     // (adicionar getters se necessário)
 }
 
+#[test]
+fn test_initialize_twice_fails() {
+    let (env, _, client) = create_contract();
+    let admin = Address::generate(&env);
+    
+    client.initialize(&admin);
+    
+    let attacker = Address::generate(&env);
+    assert_eq!(client.try_initialize(&attacker), Err(Ok(CreditScoreError::AlreadyInitialized)));
+}
+
 #[test]
 fn test_update_credit_score_new_user() {
     let (env, _, client) = create_contract();
@@ -140,7 +151,6 @@ fn test_risk_level_classification() {
 }
 
 #[test]
-#[should_panic(expected = "Loan contract not set")]
 fn test_record_payment_without_loan_contract() {
     let (env, _, client) = create_contract();
     let admin = Address::generate(&env);
@@ -149,7 +159,10 @@ fn test_record_payment_without_loan_contract() {
     client.initialize(&admin);
     
     // Deve falhar pois o loan contract não foi configurado
-    client.record_payment(&user, &1, &1000_0000000, &true);
+    assert_eq!(
+        client.try_record_payment(&user, &1, &1000_0000000, &true),
+        Err(Ok(CreditScoreError::LoanContractNotSet))
+    );
 }
 
 #[test]
@@ -162,4 +175,80 @@ fn test_set_loan_contract() {
     client.set_loan_contract(&loan_contract);
     
     // Se não der panic, configurou corretamente
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_guarantee_counts_against_credit_limit() {
+    let (env, _, client) = create_contract();
+    let admin = Address::generate(&env);
+    let loan_contract = Address::generate(&env);
+    let guarantor = Address::generate(&env);
+    
+    client.initialize(&admin);
+    client.set_loan_contract(&loan_contract);
+    
+    // Sem score o limite é 5k; um aval de 4k deixa só 1k livre
+    client.record_guarantee(&guarantor, &3, &4_000_0000000);
+    assert_eq!(client.get_guarantee_exposure(&guarantor), 4_000_0000000);
+    assert!(client.can_borrow(&guarantor, &1_000_0000000));
+    assert!(!client.can_borrow(&guarantor, &2_000_0000000));
+    
+    // Regravar substitui o aval do mesmo empréstimo; zero encerra
+    client.record_guarantee(&guarantor, &3, &2_000_0000000);
+    assert_eq!(client.get_guarantees(&guarantor).unwrap().len(), 1);
+    assert_eq!(client.get_guarantee_exposure(&guarantor), 2_000_0000000);
+    client.record_guarantee(&guarantor, &3, &0);
+    assert!(client.get_guarantees(&guarantor).is_none());
+    assert!(client.can_borrow(&guarantor, &5_000_0000000));
+}
+
+#[test]
+fn test_record_default() {
+    let (env, _, client) = create_contract();
+    let admin = Address::generate(&env);
+    let loan_contract = Address::generate(&env);
+    let user = Address::generate(&env);
+    
+    client.initialize(&admin);
+    client.set_loan_contract(&loan_contract);
+    
+    let off_chain_data = OffChainData {
+        bank_statements: true,
+        pix_history: false,
+        invoices: false,
+        credit_bureau: false,
+    };
+    client.update_credit_score(&user, &off_chain_data, &0);
+    
+    client.record_default(&user, &7, &5_000_0000000);
+    
+    let defaults = client.get_default_history(&user).unwrap();
+    assert_eq!(defaults.len(), 1);
+    assert_eq!(defaults.get(0).unwrap().loan_id, 7);
+    assert_eq!(defaults.get(0).unwrap().outstanding_amount, 5_000_0000000);
+    
+    let score = client.get_score(&user).unwrap();
+    assert_eq!(score.default_count, 1);
+}
+
+#[test]
+fn test_update_credit_score_extends_ttl() {
+    let (env, contract_id, client) = create_contract();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    
+    client.initialize(&admin);
+    
+    let off_chain_data = OffChainData {
+        bank_statements: true,
+        pix_history: true,
+        invoices: true,
+        credit_bureau: true,
+    };
+    client.update_credit_score(&user, &off_chain_data, &700);
+    
+    let ttl = env.as_contract(&contract_id, || {
+        env.storage().persistent().get_ttl(&("SCORE", user.clone()))
+    });
+    assert!(ttl >= 100 * 17280);
+}